@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::{Context, anyhow};
+use clap::Args;
+use rsfractal_mandelbrot::analysis::{self, Measure};
+use rsfractal_mandelbrot::history::ViewState;
+use serde::Serialize;
+
+use crate::Failure;
+use crate::view::ViewArgs;
+
+#[derive(Args, Debug)]
+pub(crate) struct AnalyzeArgs {
+    /// area or dimension
+    pub measure: Measure,
+    /// Region to analyze, the area defaults to a square view of the whole set
+    #[command(flatten)]
+    pub view: ViewArgs,
+    /// Widths to repeat the measurement at, comma separated, defaults to the view width
+    #[arg(long, value_delimiter = ',')]
+    pub resolutions: Vec<usize>,
+    /// Iteration limits to repeat the measurement at, comma separated, defaults to the view limit
+    #[arg(long, value_delimiter = ',')]
+    pub iteration_levels: Vec<usize>,
+    /// Where to write the JSON report, defaults to standard output
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Run {
+    Area(analysis::AreaEstimate),
+    Dimension(analysis::DimensionEstimate),
+}
+
+#[derive(Serialize)]
+struct Report {
+    measure: Measure,
+    view: ViewState,
+    runs: Vec<Run>,
+}
+
+pub(crate) fn run(args: AnalyzeArgs) -> Result<(), Failure> {
+    let mut base = args.view.mandelbrot()?;
+    let framed = args.view.parameters.is_some() || args.view.center.is_some() || args.view.zoom.is_some();
+    if args.measure == Measure::Area && !framed {
+        base = analysis::whole_set(&base, base.width.min(base.height));
+    }
+    let resolutions = if args.resolutions.is_empty() {
+        vec![base.width]
+    } else {
+        args.resolutions
+    };
+    let iterations = if args.iteration_levels.is_empty() {
+        vec![base.max_iterations]
+    } else {
+        args.iteration_levels
+    };
+    if resolutions.contains(&0) {
+        return Err(Failure::Input(anyhow!("resolutions must not be zero")));
+    }
+    if iterations.contains(&0) {
+        return Err(Failure::Input(anyhow!("iteration levels must not be zero")));
+    }
+
+    let mut runs = Vec::new();
+    for mandelbrot in analysis::sweep(&base, &resolutions, &iterations) {
+        let started = Instant::now();
+        let (width, height, limit) = (mandelbrot.width, mandelbrot.height, mandelbrot.max_iterations);
+        match args.measure {
+            Measure::Area => {
+                let estimate = analysis::estimate_area(&mandelbrot);
+                eprintln!(
+                    "{width}x{height} {limit} iterations: area {:.6} ± {:.6} in {:.2?}",
+                    estimate.area,
+                    estimate.error,
+                    started.elapsed()
+                );
+                runs.push(Run::Area(estimate));
+            }
+            Measure::Dimension => match analysis::estimate_dimension(&mandelbrot) {
+                Some(estimate) => {
+                    eprintln!(
+                        "{width}x{height} {limit} iterations: dimension {:.4} ± {:.4} (r² {:.4}) in {:.2?}",
+                        estimate.dimension,
+                        estimate.standard_error,
+                        estimate.r_squared,
+                        started.elapsed()
+                    );
+                    runs.push(Run::Dimension(estimate));
+                }
+                None => eprintln!("{width}x{height} {limit} iterations: too little boundary to fit a dimension"),
+            },
+        }
+    }
+
+    let report = Report {
+        measure: args.measure,
+        view: ViewState::of(&base),
+        runs,
+    };
+    let json = serde_json::to_string_pretty(&report)
+        .context("failed to serialize the report")
+        .map_err(Failure::Output)?;
+    match &args.output {
+        Some(path) => std::fs::write(path, json)
+            .with_context(|| format!("failed to write {}", path.display()))
+            .map_err(Failure::Output)?,
+        None => println!("{json}"),
+    }
+    Ok(())
+}
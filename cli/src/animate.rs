@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::{Context, anyhow};
+use clap::Args;
+use rsfractal_mandelbrot::keyframes::KeyframeFile;
+
+use crate::Failure;
+use crate::frames::FrameOutput;
+
+#[derive(Args, Debug)]
+pub(crate) struct AnimateArgs {
+    /// JSON or TOML keyframe file
+    pub keyframes: PathBuf,
+    #[arg(long)]
+    pub width: Option<usize>,
+    #[arg(long)]
+    pub height: Option<usize>,
+    /// Overrides the frame rate of the keyframe file
+    #[arg(long)]
+    pub fps: Option<f32>,
+    /// A .y4m file, - for a Y4M stream on stdout, or a directory for numbered PNG frames
+    #[arg(short, long)]
+    pub output: PathBuf,
+}
+
+pub(crate) fn run(args: AnimateArgs) -> Result<(), Failure> {
+    let mut keyframes = KeyframeFile::load(&args.keyframes)
+        .with_context(|| format!("failed to load {}", args.keyframes.display()))
+        .map_err(Failure::Input)?;
+    if let Some(fps) = args.fps {
+        if !(fps > 0.0 && fps.is_finite()) {
+            return Err(Failure::Input(anyhow!("frame rate must be positive")));
+        }
+        keyframes.frame_rate = fps;
+    }
+    let base = &mut keyframes.base;
+    let width = args.width.unwrap_or(base.width);
+    let height = args.height.unwrap_or(base.height);
+    if width == 0 || height == 0 {
+        return Err(Failure::Input(anyhow!("resolution must not be zero")));
+    }
+    base.set_resolution(width, height);
+
+    let started = Instant::now();
+    let mut output = FrameOutput::create(&args.output, width, height, keyframes.frame_rate)?;
+    let mut pixels = vec![0u8; width * height * 4];
+    for (index, frame) in keyframes.frames().enumerate() {
+        frame.render(&mut pixels);
+        output.write(index, &frame, &pixels)?;
+    }
+    output.finish()?;
+
+    eprintln!(
+        "{} frames of {width}x{height} in {:.2?} -> {}",
+        keyframes.frame_count(),
+        started.elapsed(),
+        args.output.display()
+    );
+    Ok(())
+}
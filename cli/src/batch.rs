@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use anyhow::{Context, anyhow};
+use clap::Args;
+use rsfractal_mandelbrot::jobs::{Job, JobFile};
+use rsfractal_mandelbrot::mandelbrot::{Field, Mandelbrot};
+use serde::Serialize;
+
+use crate::Failure;
+use crate::output::{image_format, save_image};
+
+#[derive(Args, Debug)]
+pub(crate) struct BatchArgs {
+    /// JSON or TOML job file
+    pub jobs: PathBuf,
+    /// Size of the thread pool shared by all jobs, defaults to the number of cores
+    #[arg(long)]
+    pub threads: Option<usize>,
+    /// Render jobs whose output already exists again
+    #[arg(long)]
+    pub force: bool,
+    /// Where to write the JSON summary, defaults to the job file with a .summary.json extension
+    #[arg(long)]
+    pub summary: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Rendered,
+    Skipped,
+    Failed,
+}
+
+#[derive(Serialize)]
+struct Statistics {
+    max_iterations: usize,
+    mean_iterations: f64,
+    /// Highest iteration count of an escaping pixel.
+    max_escaped: u32,
+    /// Share of pixels that reached the iteration limit.
+    interior: f64,
+}
+
+#[derive(Serialize)]
+struct JobSummary {
+    name: String,
+    output: PathBuf,
+    priority: i32,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    seconds: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    statistics: Option<Statistics>,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    rendered: usize,
+    skipped: usize,
+    failed: usize,
+    seconds: f64,
+    jobs: Vec<JobSummary>,
+}
+
+pub(crate) fn run(args: BatchArgs) -> Result<(), Failure> {
+    let file = JobFile::load(&args.jobs)
+        .with_context(|| format!("failed to load {}", args.jobs.display()))
+        .map_err(Failure::Input)?;
+    let directory = args.jobs.parent().unwrap_or(Path::new("."));
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.threads.unwrap_or(0))
+        .build()
+        .context("failed to start the thread pool")
+        .map_err(Failure::Output)?;
+
+    let started = Instant::now();
+    let queue = file.queue();
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(queue.len()));
+    // Workers take jobs in priority order. A job that renders alone still
+    // uses the whole pool, the renderer is parallel itself.
+    pool.scope(|scope| {
+        for _ in 0..pool.current_num_threads().min(queue.len()) {
+            scope.spawn(|_| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&job) = queue.get(index) else { break };
+                    let summary = run_job(job, &file.base, directory, args.force);
+                    eprintln!("{:>8} {}", format!("{:?}", summary.status).to_lowercase(), summary.name);
+                    results.lock().unwrap().push((index, summary));
+                }
+            });
+        }
+    });
+
+    // Jobs are reported in queue order, whichever worker finished first.
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    let jobs: Vec<JobSummary> = results.into_iter().map(|(_, summary)| summary).collect();
+    let count = |status| jobs.iter().filter(|summary| summary.status == status).count();
+    let summary = Summary {
+        rendered: count(Status::Rendered),
+        skipped: count(Status::Skipped),
+        failed: count(Status::Failed),
+        seconds: started.elapsed().as_secs_f64(),
+        jobs,
+    };
+    let summary_path = args.summary.unwrap_or_else(|| args.jobs.with_extension("summary.json"));
+    let json = serde_json::to_string_pretty(&summary).context("failed to serialize the summary");
+    json.and_then(|json| Ok(std::fs::write(&summary_path, json)?))
+        .with_context(|| format!("failed to write {}", summary_path.display()))
+        .map_err(Failure::Output)?;
+
+    println!(
+        "{} rendered, {} skipped, {} failed in {:.2}s -> {}",
+        summary.rendered,
+        summary.skipped,
+        summary.failed,
+        summary.seconds,
+        summary_path.display()
+    );
+    if summary.failed > 0 {
+        return Err(Failure::Output(anyhow!(
+            "{} of {} jobs failed",
+            summary.failed,
+            summary.jobs.len()
+        )));
+    }
+    Ok(())
+}
+
+fn run_job(job: &Job, base: &Mandelbrot, directory: &Path, force: bool) -> JobSummary {
+    let started = Instant::now();
+    let output = job.output_path(directory);
+    let mut summary = JobSummary {
+        name: job.label(),
+        output: output.clone(),
+        priority: job.priority,
+        status: Status::Skipped,
+        error: None,
+        seconds: 0.0,
+        width: None,
+        height: None,
+        statistics: None,
+    };
+    if output.exists() && !force {
+        return summary;
+    }
+    match render_job(job, base, directory, &output) {
+        Ok((mandelbrot, statistics)) => {
+            summary.status = Status::Rendered;
+            summary.width = Some(mandelbrot.width);
+            summary.height = Some(mandelbrot.height);
+            summary.statistics = Some(statistics);
+        }
+        Err(error) => {
+            summary.status = Status::Failed;
+            summary.error = Some(error.to_string());
+        }
+    }
+    summary.seconds = started.elapsed().as_secs_f64();
+    summary
+}
+
+fn render_job(
+    job: &Job,
+    base: &Mandelbrot,
+    directory: &Path,
+    output: &Path,
+) -> Result<(Mandelbrot, Statistics), Failure> {
+    image_format(output)?;
+    let mandelbrot = job
+        .mandelbrot(base, directory)
+        .context("invalid job parameters")
+        .map_err(Failure::Input)?;
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))
+            .map_err(Failure::Output)?;
+    }
+    let field = mandelbrot.compute_field();
+    let mut pixels = vec![0u8; mandelbrot.width * mandelbrot.height * 4];
+    mandelbrot.colorize(&field, &mut pixels);
+    save_image(&mandelbrot, pixels, output)?;
+    let statistics = statistics(&mandelbrot, &field);
+    Ok((mandelbrot, statistics))
+}
+
+fn statistics(mandelbrot: &Mandelbrot, field: &Field) -> Statistics {
+    let count = field.samples.len().max(1) as f64;
+    let limit = mandelbrot.max_iterations as u32;
+    let total: u64 = field.samples.iter().map(|sample| sample.iterations as u64).sum();
+    let interior = field.samples.iter().filter(|sample| sample.iterations >= limit).count();
+    let max_escaped = field
+        .samples
+        .iter()
+        .map(|sample| sample.iterations)
+        .filter(|iterations| *iterations < limit)
+        .max()
+        .unwrap_or_default();
+    Statistics {
+        max_iterations: mandelbrot.max_iterations,
+        mean_iterations: total as f64 / count,
+        max_escaped,
+        interior: interior as f64 / count,
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{Context, anyhow};
+use clap::Args;
+use rsfractal_mandelbrot::autopilot::{Autopilot, Detail};
+use rsfractal_mandelbrot::jobs::{Job, JobFile};
+
+use crate::Failure;
+use crate::view::ViewArgs;
+
+#[derive(Args, Debug)]
+pub(crate) struct ExploreArgs {
+    /// Start view and the parameters every job shares
+    #[command(flatten)]
+    pub view: ViewArgs,
+    /// Seed of the random choices, the same seed takes the same path
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+    /// boundary or variance
+    #[arg(long, default_value_t = Detail::Boundary)]
+    pub detail: Detail,
+    /// Scale at which the descent stops
+    #[arg(long, default_value_t = 1e-5)]
+    pub depth: f32,
+    /// Factor the scale is multiplied with at every step
+    #[arg(long, default_value_t = 0.5)]
+    pub zoom_factor: f32,
+    #[arg(long, default_value_t = 64)]
+    pub max_steps: usize,
+    /// JSON or TOML job file with one job per view, render it with the batch command
+    #[arg(short, long, default_value = "tour.json")]
+    pub output: PathBuf,
+}
+
+pub(crate) fn run(args: ExploreArgs) -> Result<(), Failure> {
+    let start = args.view.mandelbrot()?;
+    if !(args.depth > 0.0 && args.zoom_factor > 0.0 && args.zoom_factor < 1.0) {
+        return Err(Failure::Input(anyhow!(
+            "depth must be positive and the zoom factor between 0 and 1"
+        )));
+    }
+    let mut autopilot = Autopilot::new(args.seed);
+    autopilot.detail = args.detail;
+    autopilot.zoom_factor = args.zoom_factor;
+    autopilot.target_scale = args.depth;
+    let started = Instant::now();
+    let trail = autopilot.tour(&start, args.max_steps);
+
+    // Images go next to the job file, into a directory named after it.
+    let stem = args.output.file_stem().unwrap_or("tour".as_ref());
+    let jobs = trail
+        .iter()
+        .enumerate()
+        .map(|(step, view)| {
+            Ok(Job {
+                name: Some(format!("step {step}")),
+                output: Path::new(stem).join(format!("step_{step:03}.png")),
+                parameters: None,
+                priority: 0,
+                overrides: serde_json::to_value(view)?,
+            })
+        })
+        .collect::<serde_json::Result<Vec<Job>>>()
+        .context("failed to serialize the views")
+        .map_err(Failure::Output)?;
+    JobFile::new(start, jobs)
+        .save(&args.output)
+        .with_context(|| format!("failed to write {}", args.output.display()))
+        .map_err(Failure::Output)?;
+
+    let last = trail.last().expect("the trail starts with the start view");
+    println!(
+        "{} views down to scale {:.3e} at {},{} in {:.2?} -> {}",
+        trail.len(),
+        last.scale,
+        last.position.x,
+        last.position.y,
+        started.elapsed(),
+        args.output.display()
+    );
+    Ok(())
+}
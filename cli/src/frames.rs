@@ -0,0 +1,71 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use rsfractal_mandelbrot::mandelbrot::Mandelbrot;
+
+use crate::Failure;
+use crate::y4m::Y4mWriter;
+
+/// Destination of an animation: a .y4m file, `-` for a Y4M stream on stdout,
+/// or a directory that receives numbered PNG frames with their parameters.
+pub(crate) enum FrameOutput {
+    Y4m(Y4mWriter<BufWriter<Box<dyn Write>>>),
+    Directory(PathBuf),
+}
+
+impl FrameOutput {
+    pub(crate) fn create(path: &Path, width: usize, height: usize, frame_rate: f32) -> Result<Self, Failure> {
+        let stdout = path.as_os_str() == "-";
+        let y4m = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("y4m"));
+        let result = if stdout || y4m {
+            let writer: std::io::Result<Box<dyn Write>> = if stdout {
+                Ok(Box::new(std::io::stdout().lock()))
+            } else {
+                File::create(path).map(|file| Box::new(file) as Box<dyn Write>)
+            };
+            writer.and_then(|writer| {
+                Y4mWriter::new(BufWriter::new(writer), width, height, frame_rate).map(FrameOutput::Y4m)
+            })
+        } else {
+            std::fs::create_dir_all(path).map(|()| FrameOutput::Directory(path.to_path_buf()))
+        };
+        result
+            .with_context(|| format!("failed to create {}", path.display()))
+            .map_err(Failure::Output)
+    }
+
+    pub(crate) fn write(&mut self, index: usize, frame: &Mandelbrot, pixels: &[u8]) -> Result<(), Failure> {
+        match self {
+            FrameOutput::Y4m(writer) => writer
+                .write_frame(pixels)
+                .context("failed to write Y4M frame")
+                .map_err(Failure::Output),
+            FrameOutput::Directory(directory) => {
+                let path = directory.join(format!("frame_{index:05}.png"));
+                // Progress goes to stderr, stdout may carry a Y4M stream in the other mode.
+                eprint!("\rframe {}", index + 1);
+                frame
+                    .save_png(&path, pixels)
+                    .with_context(|| format!("failed to write {}", path.display()))
+                    .map_err(Failure::Output)
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> Result<(), Failure> {
+        match self {
+            FrameOutput::Y4m(writer) => writer
+                .finish()
+                .context("failed to write Y4M stream")
+                .map_err(Failure::Output),
+            FrameOutput::Directory(_) => {
+                eprintln!();
+                Ok(())
+            }
+        }
+    }
+}
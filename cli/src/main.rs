@@ -0,0 +1,94 @@
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+mod analyze;
+mod animate;
+mod batch;
+mod explore;
+mod frames;
+mod output;
+mod poster;
+mod render;
+mod view;
+mod y4m;
+mod zoom;
+
+/// Failure of a command, mapped to the process exit code. Usage errors are
+/// reported by clap with exit code 2.
+#[derive(Debug)]
+pub(crate) enum Failure {
+    /// Parameter files, palettes or flag values that cannot be used.
+    Input(anyhow::Error),
+    /// The result could not be encoded or written.
+    Output(anyhow::Error),
+}
+
+impl Failure {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Failure::Input(_) => ExitCode::from(3),
+            Failure::Output(_) => ExitCode::from(4),
+        }
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (Failure::Input(error) | Failure::Output(error)) = self;
+        // Library errors often include their source in the message already.
+        let mut message = error.to_string();
+        for cause in error.chain().skip(1) {
+            let cause = cause.to_string();
+            if !message.contains(&cause) {
+                message = format!("{message}: {cause}");
+            }
+        }
+        f.write_str(&message)
+    }
+}
+
+#[derive(Parser)]
+#[command(name = "rsfractal", version, about = "Headless Mandelbrot renderer")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render a single image
+    Render(render::RenderArgs),
+    /// Render a zoom animation as PNG frames or a Y4M stream
+    Zoom(zoom::ZoomArgs),
+    /// Render a keyframed camera path as PNG frames or a Y4M stream
+    Animate(animate::AnimateArgs),
+    /// Render a large image in bands that are streamed to disk, resuming interrupted jobs
+    Poster(poster::PosterArgs),
+    /// Render a queue of jobs from a job file and write a JSON summary
+    Batch(batch::BatchArgs),
+    /// Zoom into the most detailed regions and write the views as a job file
+    Explore(explore::ExploreArgs),
+    /// Estimate the area of the set or the dimension of its boundary across resolutions and iteration limits
+    Analyze(analyze::AnalyzeArgs),
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Render(args) => render::run(args),
+        Command::Zoom(args) => zoom::run(args),
+        Command::Animate(args) => animate::run(args),
+        Command::Poster(args) => poster::run(args),
+        Command::Batch(args) => batch::run(args),
+        Command::Explore(args) => explore::run(args),
+        Command::Analyze(args) => analyze::run(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("error: {failure}");
+            failure.exit_code()
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use anyhow::{Context, anyhow};
+use image::{ImageFormat, RgbaImage};
+use rsfractal_mandelbrot::mandelbrot::Mandelbrot;
+
+use crate::Failure;
+
+/// Output format from the file extension. Only PNG can carry the parameters.
+pub(crate) fn image_format(path: &Path) -> Result<ImageFormat, Failure> {
+    match ImageFormat::from_path(path) {
+        Ok(format @ (ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP)) => Ok(format),
+        _ => Err(Failure::Input(anyhow!(
+            "unsupported output format '{}', expected .png, .jpg or .webp",
+            path.display()
+        ))),
+    }
+}
+
+/// Writes RGBA8 `pixels` of the current resolution to `path`, PNG files get the parameters embedded.
+pub(crate) fn save_image(mandelbrot: &Mandelbrot, pixels: Vec<u8>, path: &Path) -> Result<(), Failure> {
+    let result = match image_format(path)? {
+        ImageFormat::Png => mandelbrot.save_png(path, &pixels).map_err(anyhow::Error::from),
+        format => {
+            let image = RgbaImage::from_raw(mandelbrot.width as u32, mandelbrot.height as u32, pixels)
+                .context("pixel buffer does not match the resolution")
+                .map_err(Failure::Output)?;
+            // JPEG has no alpha channel and the renderer writes opaque pixels anyway.
+            let image = image::DynamicImage::ImageRgba8(image).to_rgb8();
+            image.save_with_format(path, format).map_err(anyhow::Error::from)
+        }
+    };
+    result
+        .with_context(|| format!("failed to write {}", path.display()))
+        .map_err(Failure::Output)
+}
@@ -0,0 +1,229 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{Context, anyhow};
+use clap::Args;
+use rsfractal_mandelbrot::poster::Poster;
+use serde::{Deserialize, Serialize};
+use tiff::encoder::{TiffEncoder, TiffKind, colortype};
+use tiff::tags::Tag;
+
+use crate::Failure;
+use crate::view::ViewArgs;
+
+#[derive(Args, Debug)]
+pub(crate) struct PosterArgs {
+    #[command(flatten)]
+    pub view: ViewArgs,
+    /// Rows rendered at a time, memory use grows with width times band height
+    #[arg(long, default_value_t = 128)]
+    pub band_height: usize,
+    /// Output image, .png or uncompressed .tif/.tiff
+    #[arg(short, long, default_value = "poster.png")]
+    pub output: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PosterFormat {
+    Png,
+    Tiff,
+}
+
+/// Progress of an interrupted job, stored next to the finished bands.
+#[derive(Serialize, Deserialize, PartialEq)]
+struct Checkpoint {
+    parameters: serde_json::Value,
+    band_height: usize,
+    completed: usize,
+}
+
+pub(crate) fn run(args: PosterArgs) -> Result<(), Failure> {
+    let format = poster_format(&args.output)?;
+    let mandelbrot = args.view.mandelbrot()?;
+    let poster = Poster::new(mandelbrot, args.band_height);
+    let (width, height) = (poster.mandelbrot.width, poster.mandelbrot.height);
+    if u32::try_from(width).is_err() || u32::try_from(height).is_err() {
+        return Err(Failure::Input(anyhow!("resolution {width}x{height} is too large")));
+    }
+
+    let started = Instant::now();
+    // Bands are kept as small PNG files until the whole image is rendered, so
+    // an interrupted job only loses the band it was working on.
+    let bands = partial_directory(&args.output);
+    render_bands(&poster, &bands)
+        .with_context(|| format!("failed to render bands into {}", bands.display()))
+        .map_err(Failure::Output)?;
+    assemble(&poster, &bands, format, &args.output)
+        .and_then(|()| Ok(std::fs::remove_dir_all(&bands)?))
+        .with_context(|| format!("failed to write {}", args.output.display()))
+        .map_err(Failure::Output)?;
+
+    println!(
+        "{width}x{height} in {:.2?} -> {}",
+        started.elapsed(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+fn poster_format(path: &Path) -> Result<PosterFormat, Failure> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    match extension.to_ascii_lowercase().as_str() {
+        "png" => Ok(PosterFormat::Png),
+        "tif" | "tiff" => Ok(PosterFormat::Tiff),
+        _ => Err(Failure::Input(anyhow!(
+            "unsupported poster format '{}', expected .png, .tif or .tiff",
+            path.display()
+        ))),
+    }
+}
+
+fn partial_directory(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_owned();
+    name.push(".partial");
+    PathBuf::from(name)
+}
+
+fn band_path(bands: &Path, index: usize) -> PathBuf {
+    bands.join(format!("band_{index:05}.png"))
+}
+
+/// Renders every band that is not yet on disk. A checkpoint of a different
+/// job is discarded.
+fn render_bands(poster: &Poster, bands: &Path) -> anyhow::Result<()> {
+    let mut checkpoint = Checkpoint {
+        parameters: serde_json::to_value(&poster.mandelbrot)?,
+        band_height: poster.band_height,
+        completed: 0,
+    };
+    let checkpoint_path = bands.join("checkpoint.json");
+    if let Ok(source) = std::fs::read_to_string(&checkpoint_path) {
+        match serde_json::from_str::<Checkpoint>(&source) {
+            Ok(previous)
+                if previous.parameters == checkpoint.parameters && previous.band_height == checkpoint.band_height =>
+            {
+                checkpoint.completed = previous.completed.min(poster.band_count());
+                eprintln!(
+                    "resuming at band {} of {}",
+                    checkpoint.completed + 1,
+                    poster.band_count()
+                );
+            }
+            _ => {
+                eprintln!("discarding checkpoint of a different job in {}", bands.display());
+                std::fs::remove_dir_all(bands)?;
+            }
+        }
+    }
+    std::fs::create_dir_all(bands)?;
+
+    let mut pixels = Vec::new();
+    for index in checkpoint.completed..poster.band_count() {
+        eprint!("\rband {}/{}", index + 1, poster.band_count());
+        poster.render_band(index, &mut pixels);
+        let path = band_path(bands, index);
+        write_atomic(&path, |writer| {
+            let mut encoder = png::Encoder::new(
+                writer,
+                poster.mandelbrot.width as u32,
+                poster.band_rows(index).len() as u32,
+            );
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_compression(png::Compression::Fast);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&pixels)?;
+            Ok(writer.finish()?)
+        })?;
+        checkpoint.completed = index + 1;
+        write_atomic(&checkpoint_path, |writer| {
+            Ok(serde_json::to_writer(writer, &checkpoint)?)
+        })?;
+    }
+    eprintln!();
+    Ok(())
+}
+
+/// Writes through a temporary file, so a crash never leaves a truncated file behind.
+fn write_atomic(path: &Path, write: impl FnOnce(&mut BufWriter<File>) -> anyhow::Result<()>) -> anyhow::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut writer = BufWriter::new(File::create(&temporary)?);
+    write(&mut writer)?;
+    writer.into_inner()?.sync_all()?;
+    Ok(std::fs::rename(&temporary, path)?)
+}
+
+fn read_band(bands: &Path, index: usize, pixels: &mut Vec<u8>) -> anyhow::Result<()> {
+    let path = band_path(bands, index);
+    let file = File::open(&path).with_context(|| format!("missing band {}", path.display()))?;
+    let mut reader = png::Decoder::new(BufReader::new(file)).read_info()?;
+    pixels.resize(reader.output_buffer_size().context("band is too large")?, 0);
+    reader.next_frame(pixels)?;
+    Ok(())
+}
+
+/// Streams the bands into the final image one at a time.
+fn assemble(poster: &Poster, bands: &Path, format: PosterFormat, output: &Path) -> anyhow::Result<()> {
+    let mandelbrot = &poster.mandelbrot;
+    let parameters = mandelbrot.to_json()?;
+    let mut writer = BufWriter::new(File::create(output)?);
+    let mut pixels = Vec::new();
+    match format {
+        PosterFormat::Png => {
+            let mut encoder = png::Encoder::new(&mut writer, mandelbrot.width as u32, mandelbrot.height as u32);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.add_itxt_chunk(rsfractal_mandelbrot::parameters::PNG_KEYWORD.to_string(), parameters)?;
+            let mut png = encoder.write_header()?;
+            let mut stream = png.stream_writer()?;
+            for index in 0..poster.band_count() {
+                read_band(bands, index, &mut pixels)?;
+                stream.write_all(&pixels)?;
+            }
+            stream.finish()?;
+        }
+        PosterFormat::Tiff => {
+            // Classic TIFF offsets are 32 bits, larger images need BigTIFF.
+            if mandelbrot.width * mandelbrot.height * 4 < u32::MAX as usize / 2 {
+                write_tiff(TiffEncoder::new(&mut writer)?, poster, bands, &parameters, &mut pixels)?;
+            } else {
+                write_tiff(
+                    TiffEncoder::new_big(&mut writer)?,
+                    poster,
+                    bands,
+                    &parameters,
+                    &mut pixels,
+                )?;
+            }
+        }
+    }
+    writer.into_inner()?.sync_all()?;
+    Ok(())
+}
+
+/// Writes one strip per band. The encoder only compresses images written in
+/// one piece, so streamed strips stay uncompressed.
+fn write_tiff<W: Write + Seek, K: TiffKind>(
+    mut encoder: TiffEncoder<W, K>,
+    poster: &Poster,
+    bands: &Path,
+    parameters: &str,
+    pixels: &mut Vec<u8>,
+) -> anyhow::Result<()> {
+    let mandelbrot = &poster.mandelbrot;
+    let mut image = encoder.new_image::<colortype::RGBA8>(mandelbrot.width as u32, mandelbrot.height as u32)?;
+    image.encoder().write_tag(Tag::ImageDescription, parameters)?;
+    image.rows_per_strip(poster.band_height as u32)?;
+    for index in 0..poster.band_count() {
+        read_band(bands, index, pixels)?;
+        image.write_strip(pixels)?;
+    }
+    image.finish()?;
+    Ok(())
+}
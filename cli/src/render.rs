@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
+use clap::Args;
+use rsfractal_mandelbrot::rays::{self, Angle, Landing, RayTracer};
+
+use crate::Failure;
+use crate::output::{image_format, save_image};
+use crate::view::ViewArgs;
+
+#[derive(Args, Debug)]
+pub(crate) struct RenderArgs {
+    #[command(flatten)]
+    pub view: ViewArgs,
+    /// Output image, the format follows the extension (.png, .jpg, .webp)
+    #[arg(short, long, default_value = "fractal.png")]
+    pub output: PathBuf,
+    /// External ray to draw over the image, as p/q or a binary expansion like .0(01), repeatable
+    #[arg(long = "ray")]
+    pub rays: Vec<Angle>,
+}
+
+pub(crate) fn run(args: RenderArgs) -> Result<(), Failure> {
+    let mandelbrot = args.view.mandelbrot()?;
+    image_format(&args.output)?;
+
+    let start = Instant::now();
+    let mut pixels = vec![0u8; mandelbrot.width * mandelbrot.height * 4];
+    mandelbrot.render(&mut pixels);
+    let tracer = RayTracer::default();
+    for angle in &args.rays {
+        rays::draw_polyline(&mandelbrot, &tracer.trace(*angle), [255, 255, 255, 255], &mut pixels);
+        match rays::landing_point(&tracer, *angle) {
+            Ok(Landing::Root { position, period }) => {
+                println!("ray {angle} lands on the root of a period {period} component at {position}")
+            }
+            Ok(Landing::Misiurewicz(point)) => println!(
+                "ray {angle} lands on the Misiurewicz point {},{} at {}",
+                point.preperiod, point.period, point.position
+            ),
+            Err(error) => eprintln!("ray {angle}: {error}"),
+        }
+    }
+    let elapsed = start.elapsed();
+
+    save_image(&mandelbrot, pixels, &args.output)?;
+    println!(
+        "{}x{} in {:.2?} -> {}",
+        mandelbrot.width,
+        mandelbrot.height,
+        elapsed,
+        args.output.display()
+    );
+    Ok(())
+}
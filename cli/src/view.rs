@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, anyhow};
+use clap::Args;
+use rsfractal_mandelbrot::mandelbrot::{Aspect, Coloring, Mandelbrot, Rendering};
+use rsfractal_mandelbrot::palette::PaletteFormat;
+use rsfractal_mandelbrot::vector::Vector;
+
+use crate::Failure;
+
+/// View and coloring flags shared by the commands. Flags override the
+/// values read from `--parameters`.
+#[derive(Args, Debug, Clone)]
+pub(crate) struct ViewArgs {
+    /// JSON, TOML or PNG file with the starting parameters
+    #[arg(short, long)]
+    pub parameters: Option<PathBuf>,
+    #[arg(long)]
+    pub width: Option<usize>,
+    #[arg(long)]
+    pub height: Option<usize>,
+    /// Center of the view as RE,IM
+    #[arg(short, long, value_parser = parse_vector, allow_hyphen_values = true)]
+    pub center: Option<Vector>,
+    /// Half size of the view in the complex plane, along the shorter side unless --aspect says otherwise
+    #[arg(short, long)]
+    pub zoom: Option<f32>,
+    /// fit, fill or stretch, how the zoom applies to non-square outputs
+    #[arg(long)]
+    pub aspect: Option<Aspect>,
+    #[arg(short, long)]
+    pub iterations: Option<usize>,
+    #[arg(long)]
+    pub bailout: Option<f32>,
+    /// Iterations between cycle checks, the period coloring finds cycles up to one longer
+    #[arg(long)]
+    pub period_length: Option<usize>,
+    /// smooth or fast
+    #[arg(long)]
+    pub rendering: Option<Rendering>,
+    /// palette, lch, orbittrap, stripe, tia, atomdomain or period
+    #[arg(long)]
+    pub coloring: Option<Coloring>,
+    /// Name of a built-in palette or a .ggr, .map, .cpt or .svg file
+    #[arg(long)]
+    pub palette: Option<String>,
+    #[arg(long)]
+    pub exponent: Option<f32>,
+    /// Counterclockwise rotation of the view in degrees
+    #[arg(long, allow_hyphen_values = true)]
+    pub rotation: Option<f32>,
+    /// Horizontal shear of the view
+    #[arg(long, allow_hyphen_values = true)]
+    pub skew: Option<f32>,
+    /// Mirror the view horizontally
+    #[arg(long)]
+    pub flip: bool,
+}
+
+impl ViewArgs {
+    pub(crate) fn mandelbrot(&self) -> Result<Mandelbrot, Failure> {
+        let mut mandelbrot = match &self.parameters {
+            Some(path) => Mandelbrot::load(path)
+                .with_context(|| format!("failed to load {}", path.display()))
+                .map_err(Failure::Input)?,
+            None => Mandelbrot::default(),
+        };
+
+        let width = self.width.unwrap_or(mandelbrot.width);
+        let height = self.height.unwrap_or(mandelbrot.height);
+        if width == 0 || height == 0 {
+            return Err(Failure::Input(anyhow!("resolution must not be zero")));
+        }
+        mandelbrot.set_resolution(width, height);
+        if let Some(zoom) = self.zoom {
+            if !(zoom.is_finite() && zoom > 0.0) {
+                return Err(Failure::Input(anyhow!("zoom must be positive")));
+            }
+            mandelbrot.scale = zoom;
+        }
+        if let Some(aspect) = self.aspect {
+            mandelbrot.aspect = aspect;
+        }
+        if let Some(center) = &self.center {
+            mandelbrot.position = center.clone();
+        }
+        if let Some(rotation) = self.rotation {
+            mandelbrot.transform.rotation = rotation;
+        }
+        if let Some(skew) = self.skew {
+            mandelbrot.transform.skew = skew;
+        }
+        if self.flip {
+            mandelbrot.transform.flip = true;
+        }
+        if let Some(iterations) = self.iterations {
+            if iterations == 0 {
+                return Err(Failure::Input(anyhow!("iterations must not be zero")));
+            }
+            mandelbrot.max_iterations = iterations;
+        }
+        if let Some(bailout) = self.bailout {
+            mandelbrot.bailout = bailout;
+        }
+        if let Some(period_length) = self.period_length {
+            mandelbrot.period_length = period_length;
+        }
+        if let Some(rendering) = &self.rendering {
+            mandelbrot.rendering = rendering.clone();
+        }
+        if let Some(exponent) = self.exponent {
+            mandelbrot.exponent = exponent;
+        }
+        if let Some(palette) = &self.palette {
+            mandelbrot.selected_palette = select_palette(&mut mandelbrot, palette)?;
+            if !mandelbrot.coloring.uses_palette() {
+                mandelbrot.coloring = Coloring::Palette;
+            }
+        }
+        if let Some(coloring) = &self.coloring {
+            mandelbrot.coloring = coloring.clone();
+        }
+        // The overrides are checked like the values of a loaded file.
+        mandelbrot.validated().map_err(|error| Failure::Input(error.into()))
+    }
+}
+
+fn select_palette(mandelbrot: &mut Mandelbrot, palette: &str) -> Result<usize, Failure> {
+    let path = Path::new(palette);
+    if PaletteFormat::from_path(path).is_some() && path.exists() {
+        return mandelbrot
+            .load_palette(path)
+            .with_context(|| format!("failed to load palette {palette}"))
+            .map_err(Failure::Input);
+    }
+    let index = mandelbrot
+        .palettes()
+        .iter()
+        .position(|candidate| candidate.name.eq_ignore_ascii_case(palette));
+    index.ok_or_else(|| {
+        let names: Vec<&str> = mandelbrot
+            .palettes()
+            .iter()
+            .map(|palette| palette.name.as_str())
+            .collect();
+        Failure::Input(anyhow!("unknown palette '{palette}', available: {}", names.join(", ")))
+    })
+}
+
+pub(crate) fn parse_vector(value: &str) -> Result<Vector, String> {
+    let (x, y) = value
+        .split_once(',')
+        .ok_or_else(|| format!("expected RE,IM but got '{value}'"))?;
+    let parse = |part: &str| part.trim().parse::<f32>().map_err(|error| format!("'{part}': {error}"));
+    Ok(Vector::new(parse(x)?, parse(y)?))
+}
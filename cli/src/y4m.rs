@@ -0,0 +1,42 @@
+use std::io::{self, Write};
+
+/// Minimal YUV4MPEG2 writer with full resolution 4:4:4 BT.601 planes, the
+/// format ffmpeg reads from a pipe without any extra flags.
+pub(crate) struct Y4mWriter<W: Write> {
+    writer: W,
+    width: usize,
+    height: usize,
+    planes: Vec<u8>,
+}
+
+impl<W: Write> Y4mWriter<W> {
+    pub(crate) fn new(mut writer: W, width: usize, height: usize, frame_rate: f32) -> io::Result<Self> {
+        // Frame rates are written as a fraction with millisecond precision.
+        let numerator = (frame_rate * 1000.0).round() as u32;
+        writeln!(writer, "YUV4MPEG2 W{width} H{height} F{numerator}:1000 Ip A1:1 C444")?;
+        Ok(Self {
+            writer,
+            width,
+            height,
+            planes: vec![0; width * height * 3],
+        })
+    }
+
+    pub(crate) fn write_frame(&mut self, rgba: &[u8]) -> io::Result<()> {
+        let size = self.width * self.height;
+        let (y_plane, chroma) = self.planes.split_at_mut(size);
+        let (u_plane, v_plane) = chroma.split_at_mut(size);
+        for (index, pixel) in rgba.chunks_exact(4).enumerate() {
+            let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+            y_plane[index] = (16.0 + 0.257 * r + 0.504 * g + 0.098 * b).round() as u8;
+            u_plane[index] = (128.0 - 0.148 * r - 0.291 * g + 0.439 * b).round() as u8;
+            v_plane[index] = (128.0 + 0.439 * r - 0.368 * g - 0.071 * b).round() as u8;
+        }
+        self.writer.write_all(b"FRAME\n")?;
+        self.writer.write_all(&self.planes)
+    }
+
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
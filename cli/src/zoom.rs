@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::anyhow;
+use clap::Args;
+use rsfractal_mandelbrot::animation::{Easing, FrameMode, ZoomAnimation};
+use rsfractal_mandelbrot::vector::Vector;
+
+use crate::Failure;
+use crate::frames::FrameOutput;
+use crate::view::{ViewArgs, parse_vector};
+
+#[derive(Args, Debug)]
+pub(crate) struct ZoomArgs {
+    /// Start view
+    #[command(flatten)]
+    pub view: ViewArgs,
+    /// Center of the last frame as RE,IM, defaults to the start center
+    #[arg(long, value_parser = parse_vector, allow_hyphen_values = true)]
+    pub end_center: Option<Vector>,
+    /// Scale of the last frame, see --zoom
+    #[arg(long)]
+    pub end_zoom: f32,
+    /// Length of the animation in seconds
+    #[arg(long, default_value_t = 10.0)]
+    pub duration: f32,
+    #[arg(long, default_value_t = 30.0)]
+    pub fps: f32,
+    /// linear, easein, easeout or easeinout
+    #[arg(long, default_value_t = Easing::EaseInOut)]
+    pub easing: Easing,
+    /// Render every frame instead of resampling shared keyframes
+    #[arg(long)]
+    pub exact: bool,
+    /// Largest keyframe size relative to the frame size
+    #[arg(long, default_value_t = 4.0)]
+    pub keyframe_scale: f32,
+    /// A .y4m file, - for a Y4M stream on stdout, or a directory for numbered PNG frames
+    #[arg(short, long)]
+    pub output: PathBuf,
+}
+
+pub(crate) fn run(args: ZoomArgs) -> Result<(), Failure> {
+    let start = args.view.mandelbrot()?;
+    if !(args.end_zoom > 0.0 && args.duration > 0.0 && args.fps > 0.0) {
+        return Err(Failure::Input(anyhow!(
+            "end zoom, duration and frame rate must be positive"
+        )));
+    }
+    let end_position = args.end_center.clone().unwrap_or_else(|| start.position.clone());
+    let (width, height) = (start.width, start.height);
+    let animation = ZoomAnimation {
+        duration: args.duration,
+        frame_rate: args.fps,
+        easing: args.easing,
+        max_keyframe_scale: args.keyframe_scale.max(1.0),
+        ..ZoomAnimation::new(start, end_position, args.end_zoom)
+    };
+    let mode = if args.exact {
+        FrameMode::Exact
+    } else {
+        FrameMode::Keyframes
+    };
+    let frames = animation.frame_count();
+    let started = Instant::now();
+
+    let mut output = FrameOutput::create(&args.output, width, height, args.fps)?;
+    animation.render(mode, |index, frame, pixels| output.write(index, frame, pixels))?;
+    output.finish()?;
+
+    eprintln!(
+        "{frames} frames of {width}x{height} in {:.2?} -> {}",
+        started.elapsed(),
+        args.output.display()
+    );
+    Ok(())
+}
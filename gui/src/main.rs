@@ -1,11 +1,22 @@
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use num_complex::Complex64;
 use pixels::{Pixels, SurfaceTexture};
 use renderer::MandelbrotRenderer;
-use rsfractal_mandelbrot::mandelbrot::{Coloring, Mandelbrot, Rendering, rect_from_position};
-use rsfractal_mandelbrot::range::Range;
+use rsfractal_mandelbrot::antialiasing::SamplePattern;
+use rsfractal_mandelbrot::autopilot::Autopilot;
+use rsfractal_mandelbrot::hdr::ToneMapping;
+use rsfractal_mandelbrot::history::{Bookmarks, History, ViewState};
+use rsfractal_mandelbrot::lighting::HeightField;
+use rsfractal_mandelbrot::mandelbrot::{Aspect, Coloring, Field, Mandelbrot, Rendering};
+use rsfractal_mandelbrot::misiurewicz::Misiurewicz;
+use rsfractal_mandelbrot::nucleus::Nucleus;
+use rsfractal_mandelbrot::orbit_trap::TrapShape;
+use rsfractal_mandelbrot::palette::{Blend, Interpolation};
+use rsfractal_mandelbrot::parameters::ParameterFormat;
+use rsfractal_mandelbrot::rays::{self, RayTracer};
 use winit::application::ApplicationHandler;
 use winit::dpi::LogicalSize;
 use winit::event::{DeviceEvent, ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent};
@@ -26,29 +37,115 @@ struct App<'a> {
     gpu_rendering: bool,
     last_frame: Option<Instant>,
     fps: f64,
+    field: Option<Field>,
+    cycling: bool,
+    history: History,
+    bookmarks: Bookmarks,
+    /// View when the current drag started.
+    drag_start: Option<ViewState>,
+    last_wheel: Option<Instant>,
+    autopilot: Option<Autopilot>,
+    last_autopilot_step: Option<Instant>,
+    /// External rays drawn over the CPU rendering.
+    rays: Vec<Vec<Complex64>>,
 }
 
 const MIN_WIDTH: u32 = 1280;
 const MIN_HEIGHT: u32 = 720;
+const BOOKMARKS_PATH: &str = "rsfractal-bookmarks.json";
+/// Wheel events closer together than this form one undo step.
+const WHEEL_GESTURE: Duration = Duration::from_millis(500);
+/// Time the autopilot shows each view.
+const AUTOPILOT_STEP: Duration = Duration::from_millis(1500);
+const RAY_COLOR: [u8; 4] = [255, 255, 255, 255];
 
 impl App<'_> {
+    /// Makes the current view the target of the next undo.
+    fn record_view(&mut self) {
+        self.history.record(ViewState::of(&self.mandelbrot));
+    }
+
+    fn show_view(&mut self, view: ViewState) {
+        view.apply(&mut self.mandelbrot);
+        self.field = None;
+        self.update_title();
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
     fn update_title(&self) {
         if let Some(window) = &self.window {
             let renderer = if self.gpu_rendering { "GPU" } else { "CPU" };
             let fps = self.fps;
+            let palette = &self.mandelbrot.palettes()[self.mandelbrot.selected_palette];
+            let cycling = if self.cycling { "On" } else { "Off" };
+            let name = format!(
+                "{} [(I)nterpolation: {}, (G)radient blend: {}, Repeat(-=): {}, (V)reverse, (Space) cycling: {cycling}]",
+                palette.name,
+                palette.interpolation(),
+                palette.blend(),
+                self.mandelbrot.palette_repeat,
+            );
             let coloring = match self.mandelbrot.coloring {
-                Coloring::Palette => {
-                    let name = &self.mandelbrot.palettes()[self.mandelbrot.selected_palette].0;
-                    format!("Palette | (P)alette: {name}")
-                }
+                Coloring::Palette => format!("Palette | (P)alette: {name}"),
                 Coloring::LCH => "LCH".to_string(),
+                Coloring::OrbitTrap => {
+                    let shape = &self.mandelbrot.trap.shape;
+                    format!("Orbit Trap | (T)rap: {shape} | (P)alette: {name}")
+                }
+                Coloring::Stripe => {
+                    let density = self.mandelbrot.stripe_density;
+                    format!("Stripe | Density([]): {density} | (P)alette: {name}")
+                }
+                Coloring::TIA => format!("TIA | (P)alette: {name}"),
+                Coloring::AtomDomain => format!("Atom Domain | (P)alette: {name}"),
+                Coloring::Period => format!("Period | (P)alette: {name}"),
             };
+            let tone_mapping = &self.mandelbrot.tone_mapping;
+            let tone = format!(
+                "T(o)ne: {} Exposure(,.): {} (D)ither: {}",
+                tone_mapping.operator,
+                tone_mapping.exposure,
+                if tone_mapping.dither { "On" } else { "Off" }
+            );
+            let transform = &self.mandelbrot.transform;
+            // Rays are drawn into the CPU frame, the GPU renderer has no overlay for them.
+            let rays = if self.gpu_rendering {
+                format!("{} (shown in CPU (M)ode)", self.rays.len())
+            } else {
+                self.rays.len().to_string()
+            };
+            let view = format!(
+                "Rotation(←→): {}° (F)lip: {} Aspect(X): {} Undo(Z) Redo(Y) (B)ookmarks(1-9): {} (N)ucleus Misiurewicz(E) Rays(Q): {} Auto(W)pilot: {}",
+                transform.rotation,
+                if transform.flip { "On" } else { "Off" },
+                self.mandelbrot.aspect,
+                self.bookmarks.bookmarks.len(),
+                rays,
+                if self.autopilot.is_some() { "On" } else { "Off" }
+            );
             let iterations = self.mandelbrot.max_iterations;
             if self.gpu_rendering {
-                window.set_title(&format!("rsfractal | (M)ode: {renderer} | (C)oloring: {coloring} | Iterations(↑↓): {iterations} | {fps:.1} fps"));
+                window.set_title(&format!("rsfractal | (M)ode: {renderer} | (C)oloring: {coloring} | {view} | {tone} | Iterations(↑↓): {iterations} | {fps:.1} fps"));
             } else {
                 let rendering = &self.mandelbrot.rendering;
-                window.set_title(&format!("rsfractal | (M)ode: {renderer} | (R)endering: {rendering} | (C)oloring: {coloring} | Iterations(↑↓): {iterations} | {fps:.1} fps"));
+                let lighting = if self.mandelbrot.lighting.enabled {
+                    format!("(L)ighting: {} (H)", self.mandelbrot.lighting.height_field)
+                } else {
+                    "(L)ighting: Off".to_string()
+                };
+                let antialiasing = &self.mandelbrot.antialiasing;
+                let antialiasing = if antialiasing.enabled() {
+                    let adaptive = if antialiasing.adaptive { "On" } else { "Off" };
+                    format!(
+                        "(A)ntialiasing: {0}x{0} {1} (J) Adaptive(K): {adaptive}",
+                        antialiasing.samples, antialiasing.pattern
+                    )
+                } else {
+                    "(A)ntialiasing: Off".to_string()
+                };
+                window.set_title(&format!("rsfractal | (M)ode: {renderer} | (R)endering: {rendering} | (C)oloring: {coloring} | {view} | {lighting} | {antialiasing} | {tone} | Iterations(↑↓): {iterations} | {fps:.1} fps"));
             }
         }
     }
@@ -93,9 +190,15 @@ impl ApplicationHandler for App<'_> {
             && let DeviceEvent::MouseMotion { delta } = event
             && self.mouse_button
         {
-            let rect = rect_from_position(&self.mandelbrot.position, &self.mandelbrot.zoom);
-            self.mandelbrot.position.x -= delta.0 as f32 * rect.width() / 1000.0;
-            self.mandelbrot.position.y -= delta.1 as f32 * rect.height() / 1000.0;
+            let extent = self.mandelbrot.extent();
+            // The drag moves along the axes of the rotated view.
+            let (dx, dy) = self.mandelbrot.transform.apply(
+                delta.0 as f32 * 2.0 * extent.x / 1000.0,
+                delta.1 as f32 * 2.0 * extent.y / 1000.0,
+            );
+            self.mandelbrot.position.x -= dx;
+            self.mandelbrot.position.y -= dy;
+            self.field = None;
             window.request_redraw();
         }
     }
@@ -121,6 +224,7 @@ impl ApplicationHandler for App<'_> {
                             self.mandelbrot
                                 .set_resolution(size.width as usize, size.height as usize);
                             let _ = pixels.resize_buffer(size.width, size.height);
+                            self.field = None;
                         }
                         window.request_redraw();
                     }
@@ -137,9 +241,15 @@ impl ApplicationHandler for App<'_> {
             } => match key {
                 KeyCode::Escape => event_loop.exit(),
                 KeyCode::KeyC => {
+                    self.field = None;
                     self.mandelbrot.coloring = match self.mandelbrot.coloring {
                         Coloring::Palette => Coloring::LCH,
-                        Coloring::LCH => Coloring::Palette,
+                        Coloring::LCH => Coloring::OrbitTrap,
+                        Coloring::OrbitTrap => Coloring::Stripe,
+                        Coloring::Stripe => Coloring::TIA,
+                        Coloring::TIA => Coloring::AtomDomain,
+                        Coloring::AtomDomain => Coloring::Period,
+                        Coloring::Period => Coloring::Palette,
                     };
                     if let (Some(pixels), Some(renderer)) = (&self.pixels, &mut self.renderer) {
                         renderer.update_coloring(pixels.device(), pixels.queue(), &self.mandelbrot);
@@ -149,7 +259,51 @@ impl ApplicationHandler for App<'_> {
                         window.request_redraw();
                     }
                 }
+                KeyCode::KeyT => {
+                    self.field = None;
+                    let trap = &mut self.mandelbrot.trap;
+                    trap.shape = match trap.shape {
+                        TrapShape::Point => TrapShape::Line,
+                        TrapShape::Line => TrapShape::Cross,
+                        TrapShape::Cross => TrapShape::Circle,
+                        TrapShape::Circle if trap.image.is_some() => TrapShape::Image,
+                        TrapShape::Circle | TrapShape::Image => TrapShape::Point,
+                    };
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                KeyCode::BracketLeft | KeyCode::BracketRight => {
+                    self.field = None;
+                    let step = if key == KeyCode::BracketLeft { -1.0 } else { 1.0 };
+                    self.mandelbrot.stripe_density = (self.mandelbrot.stripe_density + step).max(1.0);
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                KeyCode::KeyL => {
+                    self.field = None;
+                    self.mandelbrot.lighting.enabled = !self.mandelbrot.lighting.enabled;
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                KeyCode::KeyH => {
+                    self.field = None;
+                    self.mandelbrot.lighting.height_field = match self.mandelbrot.lighting.height_field {
+                        HeightField::Smooth => HeightField::Distance,
+                        HeightField::Distance => HeightField::Smooth,
+                    };
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
                 KeyCode::KeyM => {
+                    self.field = None;
                     self.gpu_rendering = !self.gpu_rendering;
                     if !self.gpu_rendering
                         && let (Some(window), Some(pixels)) = (&self.window, &mut self.pixels)
@@ -165,6 +319,7 @@ impl ApplicationHandler for App<'_> {
                     }
                 }
                 KeyCode::KeyR => {
+                    self.field = None;
                     self.mandelbrot.rendering = match self.mandelbrot.rendering {
                         Rendering::Smooth => Rendering::Fast,
                         Rendering::Fast => Rendering::Smooth,
@@ -185,7 +340,116 @@ impl ApplicationHandler for App<'_> {
                         window.request_redraw();
                     }
                 }
+                KeyCode::KeyI | KeyCode::KeyG | KeyCode::Delete => {
+                    let selected = self.mandelbrot.selected_palette;
+                    let result = if key == KeyCode::Delete {
+                        self.mandelbrot
+                            .remove_palette(selected)
+                            .map(|_| ())
+                            .ok_or("cannot remove the last palette".to_string())
+                    } else if let Some(palette) = self.mandelbrot.palette_mut(selected) {
+                        if key == KeyCode::KeyI {
+                            palette.set_interpolation(match palette.interpolation() {
+                                Interpolation::CatmullRom => Interpolation::Basis,
+                                Interpolation::Basis => Interpolation::Linear,
+                                Interpolation::Linear => Interpolation::CatmullRom,
+                            })
+                        } else {
+                            palette.set_blend(match palette.blend() {
+                                Blend::Oklab => Blend::LinearRgb,
+                                Blend::LinearRgb => Blend::LCH,
+                                Blend::LCH => Blend::Oklab,
+                            })
+                        }
+                        .map_err(|error| error.to_string())
+                    } else {
+                        Ok(())
+                    };
+                    if let Err(error) = result {
+                        eprintln!("{error}");
+                    }
+                    if let (Some(pixels), Some(renderer)) = (&self.pixels, &mut self.renderer) {
+                        renderer.update_coloring(pixels.device(), pixels.queue(), &self.mandelbrot);
+                    }
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                KeyCode::Space => {
+                    self.cycling = !self.cycling;
+                    self.last_frame = None;
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                KeyCode::KeyV | KeyCode::Minus | KeyCode::Equal => {
+                    match key {
+                        KeyCode::KeyV => self.mandelbrot.palette_reverse = !self.mandelbrot.palette_reverse,
+                        KeyCode::Minus => {
+                            self.mandelbrot.palette_repeat = (self.mandelbrot.palette_repeat - 1.0).max(1.0)
+                        }
+                        _ => self.mandelbrot.palette_repeat += 1.0,
+                    }
+                    if let (Some(pixels), Some(renderer)) = (&self.pixels, &mut self.renderer) {
+                        renderer.update_coloring(pixels.device(), pixels.queue(), &self.mandelbrot);
+                    }
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                KeyCode::KeyA | KeyCode::KeyJ | KeyCode::KeyK => {
+                    self.field = None;
+                    let antialiasing = &mut self.mandelbrot.antialiasing;
+                    match key {
+                        KeyCode::KeyA => antialiasing.samples = antialiasing.samples % 4 + 1,
+                        KeyCode::KeyJ => {
+                            antialiasing.pattern = match antialiasing.pattern {
+                                SamplePattern::Grid => SamplePattern::RotatedGrid,
+                                SamplePattern::RotatedGrid => SamplePattern::Jittered,
+                                SamplePattern::Jittered => SamplePattern::Grid,
+                            }
+                        }
+                        _ => antialiasing.adaptive = !antialiasing.adaptive,
+                    }
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                KeyCode::KeyO | KeyCode::KeyD | KeyCode::Comma | KeyCode::Period => {
+                    let tone_mapping = &mut self.mandelbrot.tone_mapping;
+                    match key {
+                        KeyCode::KeyO => {
+                            tone_mapping.operator = match tone_mapping.operator {
+                                ToneMapping::None => ToneMapping::Reinhard,
+                                ToneMapping::Reinhard => ToneMapping::ACES,
+                                ToneMapping::ACES => ToneMapping::None,
+                            }
+                        }
+                        KeyCode::KeyD => tone_mapping.dither = !tone_mapping.dither,
+                        KeyCode::Comma => tone_mapping.exposure -= 0.5,
+                        _ => tone_mapping.exposure += 0.5,
+                    }
+                    if let (Some(pixels), Some(renderer)) = (&self.pixels, &mut self.renderer) {
+                        renderer.update_coloring(pixels.device(), pixels.queue(), &self.mandelbrot);
+                    }
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                KeyCode::KeyS => {
+                    const SNAPSHOT_PATH: &str = "rsfractal.png";
+                    match self.mandelbrot.save(SNAPSHOT_PATH) {
+                        Ok(()) => println!("saved snapshot to {SNAPSHOT_PATH}"),
+                        Err(error) => eprintln!("{SNAPSHOT_PATH}: {error}"),
+                    }
+                }
                 KeyCode::ArrowUp => {
+                    self.field = None;
                     self.mandelbrot.max_iterations = (self.mandelbrot.max_iterations * 2).min(100000);
                     self.update_title();
                     if let Some(window) = &self.window {
@@ -193,14 +457,211 @@ impl ApplicationHandler for App<'_> {
                     }
                 }
                 KeyCode::ArrowDown => {
+                    self.field = None;
                     self.mandelbrot.max_iterations = (self.mandelbrot.max_iterations / 2).max(10);
                     self.update_title();
                     if let Some(window) = &self.window {
                         window.request_redraw();
                     }
                 }
+                KeyCode::ArrowLeft | KeyCode::ArrowRight | KeyCode::KeyF => {
+                    self.record_view();
+                    self.field = None;
+                    let transform = &mut self.mandelbrot.transform;
+                    match key {
+                        KeyCode::ArrowLeft => transform.rotation = (transform.rotation + 5.0).rem_euclid(360.0),
+                        KeyCode::ArrowRight => transform.rotation = (transform.rotation - 5.0).rem_euclid(360.0),
+                        _ => transform.flip = !transform.flip,
+                    }
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                KeyCode::KeyZ => {
+                    if let Some(view) = self.history.undo(ViewState::of(&self.mandelbrot)) {
+                        self.show_view(view);
+                    }
+                }
+                KeyCode::KeyY => {
+                    if let Some(view) = self.history.redo(ViewState::of(&self.mandelbrot)) {
+                        self.show_view(view);
+                    }
+                }
+                KeyCode::KeyB => {
+                    let name = format!("Bookmark {}", self.bookmarks.bookmarks.len() + 1);
+                    self.bookmarks.insert(&name, ViewState::of(&self.mandelbrot));
+                    match self.bookmarks.save(BOOKMARKS_PATH) {
+                        Ok(()) => println!("saved {name} to {BOOKMARKS_PATH}"),
+                        Err(error) => eprintln!("{BOOKMARKS_PATH}: {error}"),
+                    }
+                    self.update_title();
+                }
+                KeyCode::Digit1
+                | KeyCode::Digit2
+                | KeyCode::Digit3
+                | KeyCode::Digit4
+                | KeyCode::Digit5
+                | KeyCode::Digit6
+                | KeyCode::Digit7
+                | KeyCode::Digit8
+                | KeyCode::Digit9 => {
+                    let index = key as usize - KeyCode::Digit1 as usize;
+                    if let Some(bookmark) = self.bookmarks.bookmarks.get(index) {
+                        let view = bookmark.view.clone();
+                        self.record_view();
+                        self.show_view(view);
+                    }
+                }
+                KeyCode::KeyN => {
+                    let size = self.window.as_ref().map(|window| window.inner_size());
+                    let Some(size) = size else { return };
+                    let (width, height) = (size.width as f32, size.height as f32);
+                    let (cx, cy) = self.cursor_position;
+                    let c = self.mandelbrot.pixel_mapping(width, height).map(cx as f32, cy as f32);
+                    // Components within a few percent of the view around the cursor.
+                    let radius = self.mandelbrot.extent_for(width, height).y as f64 / 16.0;
+                    let c = Complex64::new(c.re as f64, c.im as f64);
+                    match Nucleus::find_near(&self.mandelbrot, c, radius) {
+                        Ok(nucleus) => {
+                            println!(
+                                "period {} nucleus at {} size {:.3e} orientation {:.1}°",
+                                nucleus.period, nucleus.position, nucleus.size, nucleus.orientation
+                            );
+                            let view = nucleus.view(&self.mandelbrot);
+                            self.record_view();
+                            self.show_view(ViewState::of(&view));
+                        }
+                        Err(error) => eprintln!("no nucleus found: {error}"),
+                    }
+                }
+                KeyCode::KeyE => {
+                    let size = self.window.as_ref().map(|window| window.inner_size());
+                    let Some(size) = size else { return };
+                    let (cx, cy) = self.cursor_position;
+                    let c = self
+                        .mandelbrot
+                        .pixel_mapping(size.width as f32, size.height as f32)
+                        .map(cx as f32, cy as f32);
+                    let c = Complex64::new(c.re as f64, c.im as f64);
+                    match Misiurewicz::find_near(&self.mandelbrot, c, 16, 4) {
+                        Ok(point) => {
+                            println!(
+                                "Misiurewicz point {},{} at {} multiplier {:.3} scale {:.3e}",
+                                point.preperiod, point.period, point.position, point.multiplier, point.scale
+                            );
+                            self.record_view();
+                            self.show_view(ViewState::of(&point.view(&self.mandelbrot)));
+                        }
+                        Err(error) => eprintln!("no Misiurewicz point found: {error}"),
+                    }
+                }
+                KeyCode::KeyQ => {
+                    // A second press hides the rays again.
+                    if self.rays.is_empty() {
+                        let size = self.window.as_ref().map(|window| window.inner_size());
+                        let Some(size) = size else { return };
+                        let (width, height) = (size.width as f32, size.height as f32);
+                        let (cx, cy) = self.cursor_position;
+                        let c = self.mandelbrot.pixel_mapping(width, height).map(cx as f32, cy as f32);
+                        let radius = self.mandelbrot.extent_for(width, height).y as f64 / 16.0;
+                        let c = Complex64::new(c.re as f64, c.im as f64);
+                        let tracer = RayTracer::default();
+                        let angles = Nucleus::find_near(&self.mandelbrot, c, radius)
+                            .map_err(|error| error.to_string())
+                            .and_then(|nucleus| {
+                                let angles = tracer.root_angles(&nucleus, self.mandelbrot.max_iterations);
+                                angles
+                                    .map(|angles| (nucleus, angles))
+                                    .map_err(|error| error.to_string())
+                            });
+                        match angles {
+                            Ok((nucleus, angles)) => {
+                                for angle in &angles {
+                                    println!(
+                                        "period {} component at {} has external angle {angle} = {}",
+                                        nucleus.period,
+                                        nucleus.position,
+                                        angle.binary()
+                                    );
+                                }
+                                self.rays = angles.into_iter().map(|angle| tracer.trace(angle)).collect();
+                            }
+                            Err(error) => eprintln!("no external angles found: {error}"),
+                        }
+                    } else {
+                        self.rays.clear();
+                    }
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                KeyCode::KeyW => {
+                    if self.autopilot.take().is_none() {
+                        self.record_view();
+                        let seed = std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .map_or(0, |time| time.as_nanos() as u64);
+                        self.autopilot = Some(Autopilot::new(seed));
+                        self.last_autopilot_step = Some(Instant::now());
+                    }
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                KeyCode::KeyX => {
+                    self.record_view();
+                    self.field = None;
+                    self.mandelbrot.aspect = match self.mandelbrot.aspect {
+                        Aspect::Fit => Aspect::Fill,
+                        Aspect::Fill => Aspect::Stretch,
+                        Aspect::Stretch => Aspect::Fit,
+                    };
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
                 _ => (),
             },
+            WindowEvent::DroppedFile(path) if ParameterFormat::from_path(&path).is_some() => {
+                match Mandelbrot::load(&path) {
+                    Ok(mut mandelbrot) => {
+                        self.record_view();
+                        // The window keeps its size and the trap image is not part of the parameters.
+                        mandelbrot.set_resolution(self.mandelbrot.width, self.mandelbrot.height);
+                        mandelbrot.trap.image = self.mandelbrot.trap.image.take();
+                        self.mandelbrot = mandelbrot;
+                        self.field = None;
+                        if let (Some(pixels), Some(renderer)) = (&self.pixels, &mut self.renderer) {
+                            renderer.update_coloring(pixels.device(), pixels.queue(), &self.mandelbrot);
+                        }
+                        self.update_title();
+                        if let Some(window) = &self.window {
+                            window.request_redraw();
+                        }
+                    }
+                    Err(error) => eprintln!("{}: {error}", path.display()),
+                }
+            }
+            WindowEvent::DroppedFile(path) => match self.mandelbrot.load_palette(&path) {
+                Ok(index) => {
+                    self.mandelbrot.selected_palette = index;
+                    if !self.mandelbrot.coloring.uses_palette() {
+                        self.mandelbrot.coloring = Coloring::Palette;
+                    }
+                    if let (Some(pixels), Some(renderer)) = (&self.pixels, &mut self.renderer) {
+                        renderer.update_coloring(pixels.device(), pixels.queue(), &self.mandelbrot);
+                    }
+                    self.update_title();
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                Err(error) => eprintln!("{}: {error}", path.display()),
+            },
             WindowEvent::CursorMoved { position, .. } => {
                 self.cursor_position = (position.x, position.y);
             }
@@ -218,17 +679,21 @@ impl ApplicationHandler for App<'_> {
                 if let Some(window) = &self.window {
                     let size = window.inner_size();
                     let (cx, cy) = self.cursor_position;
-                    let rect = rect_from_position(&self.mandelbrot.position, &self.mandelbrot.zoom);
-                    let width_range = Range::new(0.0, size.width as f32);
-                    let height_range = Range::new(0.0, size.height as f32);
-                    let real_range = Range::new(rect.start.x, rect.end.x);
-                    let imaginary_range = Range::new(rect.start.y, rect.end.y);
-                    let target_re = Range::scale(&width_range, cx as f32, &real_range);
-                    let target_im = Range::scale(&height_range, cy as f32, &imaginary_range);
-                    self.mandelbrot.zoom.x *= zoom_factor;
-                    self.mandelbrot.zoom.y *= zoom_factor;
+                    let mapping = self.mandelbrot.pixel_mapping(size.width as f32, size.height as f32);
+                    let target = mapping.map(cx as f32, cy as f32);
+                    let (target_re, target_im) = (target.re, target.im);
+                    let now = Instant::now();
+                    if self
+                        .last_wheel
+                        .is_none_or(|last| now.duration_since(last) > WHEEL_GESTURE)
+                    {
+                        self.history.record(ViewState::of(&self.mandelbrot));
+                    }
+                    self.last_wheel = Some(now);
+                    self.mandelbrot.scale *= zoom_factor;
                     self.mandelbrot.position.x = target_re + (self.mandelbrot.position.x - target_re) * zoom_factor;
                     self.mandelbrot.position.y = target_im + (self.mandelbrot.position.y - target_im) * zoom_factor;
+                    self.field = None;
                     window.request_redraw();
                 }
             }
@@ -238,6 +703,13 @@ impl ApplicationHandler for App<'_> {
                 ..
             } => {
                 self.mouse_button = state == ElementState::Pressed;
+                if self.mouse_button {
+                    self.drag_start = Some(ViewState::of(&self.mandelbrot));
+                } else if let Some(start) = self.drag_start.take()
+                    && start != ViewState::of(&self.mandelbrot)
+                {
+                    self.history.record(start);
+                }
             }
             WindowEvent::RedrawRequested => {
                 let now = Instant::now();
@@ -246,6 +718,12 @@ impl ApplicationHandler for App<'_> {
                     if dt > 0.0 {
                         self.fps = 1.0 / dt;
                     }
+                    if self.cycling {
+                        self.mandelbrot.cycle_palette(dt as f32);
+                        if let (Some(pixels), Some(renderer)) = (&self.pixels, &self.renderer) {
+                            renderer.write_coloring(pixels.queue(), &self.mandelbrot);
+                        }
+                    }
                 }
                 self.last_frame = Some(now);
 
@@ -267,11 +745,33 @@ impl ApplicationHandler for App<'_> {
                                 .unwrap();
                         }
                     } else {
-                        self.mandelbrot.render(pixels.frame_mut());
+                        let field = self.field.get_or_insert_with(|| self.mandelbrot.compute_field());
+                        self.mandelbrot.colorize(field, pixels.frame_mut());
+                        for ray in &self.rays {
+                            rays::draw_polyline(&self.mandelbrot, ray, RAY_COLOR, pixels.frame_mut());
+                        }
                         pixels.render().unwrap();
                     }
                 }
+                if let Some(autopilot) = &mut self.autopilot
+                    && self
+                        .last_autopilot_step
+                        .is_none_or(|last| now.duration_since(last) > AUTOPILOT_STEP)
+                {
+                    // Starts over from the whole set once the tour reaches its depth.
+                    let view = autopilot
+                        .step(&self.mandelbrot)
+                        .unwrap_or_else(|| ViewState::of(&Mandelbrot::default()));
+                    view.apply(&mut self.mandelbrot);
+                    self.field = None;
+                    self.last_autopilot_step = Some(now);
+                }
                 self.update_title();
+                if (self.cycling || self.autopilot.is_some())
+                    && let Some(window) = &self.window
+                {
+                    window.request_redraw();
+                }
             }
             _ => (),
         }
@@ -280,8 +780,13 @@ impl ApplicationHandler for App<'_> {
 
 fn main() -> Result<()> {
     let event_loop = EventLoop::new()?;
+    let bookmarks = Bookmarks::load(BOOKMARKS_PATH).unwrap_or_else(|error| {
+        eprintln!("{BOOKMARKS_PATH}: {error}");
+        Bookmarks::default()
+    });
     let mut app = App {
         gpu_rendering: true,
+        bookmarks,
         ..Default::default()
     };
     event_loop.run_app(&mut app)?;
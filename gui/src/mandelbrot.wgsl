@@ -4,29 +4,20 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4f
     return vec4f(positions[vertex_index], 0.0, 1.0);
 }
 
-struct Range {
-    min: f32,
-    max: f32
-}
-
-fn scale(input: Range, value: f32, output: Range) -> f32 {
-    let input_size = abs(input.max - input.min);
-    let output_size = abs(output.max - output.min);
-    return (input.max * output.min - input.min * output.max + value * output_size) / input_size;
-}
-
 struct Complex {
     re: f32,
     im: f32
 }
 
 struct Params {
-    real_range: Range,
-    @align(16) imaginary_range: Range,
-    @align(16) viewport: vec2f,
+    origin: vec2f,
+    step_x: vec2f,
+    step_y: vec2f,
     bailout: f32,
     max_iterations: u32,
     exponent: f32,
+    julia_enabled: u32,
+    julia: vec2f,
 }
 
 @group(0) @binding(0)
@@ -38,15 +29,23 @@ var color_texture: texture_2d<f32>;
 @group(0) @binding(2)
 var color_sampler: sampler;
 
+// Affine pixel to complex plane mapping, rotation and skew included.
+fn scale(pixel: vec2f) -> Complex {
+    let point = params.origin + pixel.x * params.step_x + pixel.y * params.step_y;
+    return Complex(point.x, point.y);
+}
+
 @fragment
 fn fs_main(@builtin(position) input: vec4f) -> @location(0) vec4f {
-    let c = Complex(
-        scale(Range(0.0, params.viewport.x), input.x, params.real_range),
-        scale(Range(0.0, params.viewport.y), input.y, params.imaginary_range)
-    );
+    let point = scale(input.xy);
 
     var temp: f32 = 0.0;
     var z = Complex(0.0, 0.0);
+    var c = point;
+    if params.julia_enabled != 0u {
+        z = point;
+        c = Complex(params.julia.x, params.julia.y);
+    }
     var iterations: u32 = 0u;
 
     var re2 = 0.0;
@@ -56,7 +55,7 @@ fn fs_main(@builtin(position) input: vec4f) -> @location(0) vec4f {
     q += im2;
 
     let p2 = c.re + 1.0;
-    if q * (q + (c.re - 0.25)) < 0.25 * im2 || p2 * p2 + im2 < 0.0625 {
+    if params.julia_enabled == 0u && (q * (q + (c.re - 0.25)) < 0.25 * im2 || p2 * p2 + im2 < 0.0625) {
         iterations = params.max_iterations;
     } else {
         while re2 + im2 <= params.bailout && iterations < params.max_iterations {
@@ -1,27 +1,28 @@
 use pixels::wgpu::{self, util::DeviceExt};
 use rsfractal_mandelbrot::mandelbrot::Mandelbrot;
-use rsfractal_mandelbrot::range::Range;
 
 const COLORING_SIZE: u32 = 256;
 
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 struct Params {
-    real_range: Range,
-    imaginary_range: Range,
-    viewport: [f32; 2],
+    origin: [f32; 2],
+    step_x: [f32; 2],
+    step_y: [f32; 2],
     bailout: f32,
     max_iterations: u32,
     exponent: f32,
-    _padding: [u8; 12],
+    julia_enabled: u32,
+    julia: [f32; 2],
 }
 
 fn bake_coloring_data(mandelbrot: &Mandelbrot) -> Vec<u8> {
     let mut data = vec![0u8; (COLORING_SIZE * 4) as usize];
     for i in 0..COLORING_SIZE {
         let s = i as f32 / (COLORING_SIZE - 1) as f32;
-        let color = mandelbrot.color_at(s);
-        data[i as usize * 4..i as usize * 4 + 4].copy_from_slice(&color.to_rgba8());
+        let color = mandelbrot.color_at(s).to_linear_rgba();
+        let color = mandelbrot.tone_mapping.encode_rgba8(&color, i as usize);
+        data[i as usize * 4..i as usize * 4 + 4].copy_from_slice(&color);
     }
     data
 }
@@ -41,10 +42,14 @@ fn upload_coloring_texture(device: &wgpu::Device, queue: &wgpu::Queue, data: &[u
         usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
         view_formats: &[],
     });
+    write_coloring_texture(queue, &texture, data);
+    texture
+}
 
+fn write_coloring_texture(queue: &wgpu::Queue, texture: &wgpu::Texture, data: &[u8]) {
     queue.write_texture(
         wgpu::ImageCopyTexture {
-            texture: &texture,
+            texture,
             mip_level: 0,
             origin: wgpu::Origin3d::ZERO,
             aspect: wgpu::TextureAspect::All,
@@ -61,8 +66,6 @@ fn upload_coloring_texture(device: &wgpu::Device, queue: &wgpu::Queue, data: &[u
             depth_or_array_layers: 1,
         },
     );
-
-    texture
 }
 
 #[allow(dead_code)]
@@ -218,16 +221,23 @@ impl MandelbrotRenderer {
         });
     }
 
+    /// Rewrites the coloring texture in place, used for palette cycling.
+    pub(crate) fn write_coloring(&self, queue: &wgpu::Queue, mandelbrot: &Mandelbrot) {
+        let data = bake_coloring_data(mandelbrot);
+        write_coloring_texture(queue, &self.coloring_texture, &data);
+    }
+
     pub(crate) fn set_params(&self, queue: &wgpu::Queue, mandelbrot: &Mandelbrot, viewport_width: f32, viewport_height: f32) {
-        let ranges = mandelbrot.ranges();
+        let mapping = mandelbrot.pixel_mapping(viewport_width, viewport_height);
         let params = Params {
-            real_range: ranges[2],
-            imaginary_range: ranges[3],
-            viewport: [viewport_width, viewport_height],
+            origin: [mapping.origin.re, mapping.origin.im],
+            step_x: [mapping.step_x.re, mapping.step_x.im],
+            step_y: [mapping.step_y.re, mapping.step_y.im],
             bailout: mandelbrot.bailout,
             max_iterations: mandelbrot.max_iterations as u32,
             exponent: mandelbrot.exponent,
-            _padding: [0u8; 12],
+            julia_enabled: mandelbrot.julia.is_some() as u32,
+            julia: mandelbrot.julia.as_ref().map_or([0.0; 2], |julia| [julia.x, julia.y]),
         };
         queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
     }
@@ -0,0 +1,252 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+use super::mandelbrot::{Aspect, Mandelbrot};
+use super::transform::ViewTransform;
+use super::vector::Vector;
+
+/// Quantity the analysis measures.
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum Measure {
+    /// Area of the set by pixel counting.
+    Area,
+    /// Box-counting dimension of the boundary.
+    Dimension,
+}
+
+/// What iterating a pixel revealed about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Exterior,
+    /// The orbit was caught in a cycle, or the point lies in the cardioid or the period 2 bulb.
+    Interior,
+    /// Neither escaped nor caught in a cycle within the iteration limit.
+    Undecided,
+}
+
+impl Class {
+    fn member(&self) -> bool {
+        *self != Class::Exterior
+    }
+}
+
+/// Area of the set within one view, with bounds from the pixel classification.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AreaEstimate {
+    pub width: usize,
+    pub height: usize,
+    pub max_iterations: usize,
+    pub interior: usize,
+    pub exterior: usize,
+    pub undecided: usize,
+    /// Pixels with a 4-neighbor on the other side of the boundary.
+    pub boundary: usize,
+    pub pixel_area: f64,
+    /// Interior pixels away from the boundary.
+    pub lower: f64,
+    /// Every pixel that did not escape plus the exterior pixels next to one.
+    pub upper: f64,
+    /// Midpoint of the bounds.
+    pub area: f64,
+    /// Half the distance between the bounds.
+    pub error: f64,
+}
+
+/// Number of boxes of one size that contain boundary pixels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoxCount {
+    /// Side of the boxes in the complex plane.
+    pub size: f64,
+    pub boxes: usize,
+}
+
+/// Box-counting dimension of the boundary within one view: the slope of
+/// log(boxes) over log(1 / size), fitted by least squares.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DimensionEstimate {
+    pub width: usize,
+    pub height: usize,
+    pub max_iterations: usize,
+    pub boundary: usize,
+    pub counts: Vec<BoxCount>,
+    pub dimension: f64,
+    pub standard_error: f64,
+    pub r_squared: f64,
+}
+
+/// Square view of `base` at `resolution` that covers the whole set,
+/// [-2, 0.5] × [-1.25, 1.25].
+pub fn whole_set(base: &Mandelbrot, resolution: usize) -> Mandelbrot {
+    let mut mandelbrot = base.clone();
+    mandelbrot.set_resolution(resolution, resolution);
+    mandelbrot.position = Vector::new(-0.75, 0.0);
+    mandelbrot.scale = 1.25;
+    mandelbrot.aspect = Aspect::Stretch;
+    mandelbrot.transform = ViewTransform::default();
+    mandelbrot
+}
+
+/// Classifies every pixel of the view of `mandelbrot`, row by row.
+pub fn classify(mandelbrot: &Mandelbrot) -> Vec<Class> {
+    let mapping = mandelbrot.mapping();
+    (0..mandelbrot.width * mandelbrot.height)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = ((index % mandelbrot.width) as f32, (index / mandelbrot.width) as f32);
+            let orbit = mandelbrot.escape(&mapping.map(x, y));
+            if orbit.iterations < mandelbrot.max_iterations {
+                Class::Exterior
+            } else if orbit.period > 0 {
+                Class::Interior
+            } else {
+                Class::Undecided
+            }
+        })
+        .collect()
+}
+
+/// Marks the pixels that have a 4-neighbor on the other side of the boundary.
+fn boundary(classes: &[Class], width: usize, height: usize) -> Vec<bool> {
+    (0..classes.len())
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            let member = classes[index].member();
+            [
+                (x > 0).then(|| index - 1),
+                (x + 1 < width).then(|| index + 1),
+                (y > 0).then(|| index - width),
+                (y + 1 < height).then(|| index + width),
+            ]
+            .into_iter()
+            .flatten()
+            .any(|neighbor| classes[neighbor].member() != member)
+        })
+        .collect()
+}
+
+/// Area of a pixel of the view, which the transform may shear.
+fn pixel_area(mandelbrot: &Mandelbrot) -> f64 {
+    let mapping = mandelbrot.mapping();
+    let (x, y) = (mapping.step_x, mapping.step_y);
+    (x.re as f64 * y.im as f64 - x.im as f64 * y.re as f64).abs()
+}
+
+/// Estimates the area of the part of the set within the view of `mandelbrot`.
+pub fn estimate_area(mandelbrot: &Mandelbrot) -> AreaEstimate {
+    let classes = classify(mandelbrot);
+    let edges = boundary(&classes, mandelbrot.width, mandelbrot.height);
+    let count = |class| classes.iter().filter(|candidate| **candidate == class).count();
+    let (interior, exterior, undecided) = (count(Class::Interior), count(Class::Exterior), count(Class::Undecided));
+    let certain = classes
+        .iter()
+        .zip(&edges)
+        .filter(|(class, edge)| **class == Class::Interior && !**edge)
+        .count();
+    let touching = classes
+        .iter()
+        .zip(&edges)
+        .filter(|(class, edge)| **class == Class::Exterior && **edge)
+        .count();
+    let pixel_area = pixel_area(mandelbrot);
+    let lower = certain as f64 * pixel_area;
+    let upper = (interior + undecided + touching) as f64 * pixel_area;
+    AreaEstimate {
+        width: mandelbrot.width,
+        height: mandelbrot.height,
+        max_iterations: mandelbrot.max_iterations,
+        interior,
+        exterior,
+        undecided,
+        boundary: edges.iter().filter(|edge| **edge).count(),
+        pixel_area,
+        lower,
+        upper,
+        area: (lower + upper) / 2.0,
+        error: (upper - lower) / 2.0,
+    }
+}
+
+/// Estimates the box-counting dimension of the boundary within the view of
+/// `mandelbrot` from boxes of 1, 2, 4, … pixels, as long as at least 8 of
+/// them fit across the view. `None` if fewer than two box sizes contain boundary.
+pub fn estimate_dimension(mandelbrot: &Mandelbrot) -> Option<DimensionEstimate> {
+    let (width, height) = (mandelbrot.width, mandelbrot.height);
+    let classes = classify(mandelbrot);
+    let edges = boundary(&classes, width, height);
+    let pixel_size = pixel_area(mandelbrot).sqrt();
+    let mut counts = Vec::new();
+    let mut size = 1;
+    while size * 8 <= width.min(height) {
+        let columns = width.div_ceil(size);
+        let mut occupied = vec![false; columns * height.div_ceil(size)];
+        for (index, _) in edges.iter().enumerate().filter(|(_, edge)| **edge) {
+            occupied[(index / width / size) * columns + index % width / size] = true;
+        }
+        let boxes = occupied.iter().filter(|occupied| **occupied).count();
+        if boxes > 0 {
+            counts.push(BoxCount {
+                size: size as f64 * pixel_size,
+                boxes,
+            });
+        }
+        size *= 2;
+    }
+    let points: Vec<(f64, f64)> = counts
+        .iter()
+        .map(|count| ((1.0 / count.size).ln(), (count.boxes as f64).ln()))
+        .collect();
+    let (dimension, standard_error, r_squared) = fit_line(&points)?;
+    Some(DimensionEstimate {
+        width,
+        height,
+        max_iterations: mandelbrot.max_iterations,
+        boundary: edges.iter().filter(|edge| **edge).count(),
+        counts,
+        dimension,
+        standard_error,
+        r_squared,
+    })
+}
+
+/// Least squares slope of `points`, its standard error and the coefficient of determination.
+fn fit_line(points: &[(f64, f64)]) -> Option<(f64, f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let syy: f64 = points.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+    let slope = sxy / sxx;
+    let residual = (syy - slope * sxy).max(0.0);
+    // A line through two points fits exactly and leaves no residual to judge it by.
+    let standard_error = if points.len() > 2 {
+        (residual / (n - 2.0) / sxx).sqrt()
+    } else {
+        0.0
+    };
+    let r_squared = if syy > 0.0 { 1.0 - residual / syy } else { 1.0 };
+    Some((slope, standard_error, r_squared))
+}
+
+/// Views of `base` at every combination of `resolutions` (widths, the height
+/// follows the aspect ratio of `base`) and iteration limits, coarsest first.
+/// Neither may contain zero.
+pub fn sweep(base: &Mandelbrot, resolutions: &[usize], iterations: &[usize]) -> Vec<Mandelbrot> {
+    resolutions
+        .iter()
+        .flat_map(|&width| {
+            iterations.iter().map(move |&max_iterations| {
+                let mut mandelbrot = base.clone();
+                mandelbrot.set_resolution(width, (width * base.height).div_ceil(base.width));
+                mandelbrot.max_iterations = max_iterations;
+                mandelbrot
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,229 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+use super::mandelbrot::{Aspect, Mandelbrot};
+use super::rectangle::Rectangle;
+use super::transform::PixelMapping;
+use super::vector::Vector;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString, EnumIter)]
+#[strum(ascii_case_insensitive)]
+pub enum FrameMode {
+    /// Every frame is rendered on its own.
+    Exact,
+    /// Frames are resampled from one larger keyframe per zoom octave.
+    Keyframes,
+}
+
+/// Zoom from the view of `start` to `end_position`/`end_zoom`, where the zoom
+/// is the scale of the view. Everything except the view is taken from `start`.
+#[derive(Debug, Clone)]
+pub struct ZoomAnimation {
+    pub start: Mandelbrot,
+    pub end_position: Vector,
+    pub end_zoom: f32,
+    pub duration: f32,
+    pub frame_rate: f32,
+    pub easing: Easing,
+    /// Upper bound for the keyframe size relative to the frame size.
+    pub max_keyframe_scale: f32,
+}
+
+impl ZoomAnimation {
+    pub fn new(start: Mandelbrot, end_position: Vector, end_zoom: f32) -> Self {
+        Self {
+            start,
+            end_position,
+            end_zoom,
+            duration: 10.0,
+            frame_rate: 30.0,
+            easing: Easing::EaseInOut,
+            max_keyframe_scale: 4.0,
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        ((self.duration * self.frame_rate).round() as usize).max(1)
+    }
+
+    /// View at `t` in [0, 1]. The zoom is interpolated exponentially and the
+    /// center moves in proportion to the zoom, so the target approaches the
+    /// middle of the screen at a steady on-screen rate.
+    pub fn view_at(&self, t: f32) -> (Vector, f32) {
+        let s = self.easing.apply(t);
+        let start_zoom = self.start.scale;
+        let zoom = start_zoom * (self.end_zoom / start_zoom).powf(s);
+        let progress = if (start_zoom - self.end_zoom).abs() > f32::EPSILON * start_zoom {
+            (start_zoom - zoom) / (start_zoom - self.end_zoom)
+        } else {
+            s
+        };
+        let start = &self.start.position;
+        let position = Vector::new(
+            start.x + (self.end_position.x - start.x) * progress,
+            start.y + (self.end_position.y - start.y) * progress,
+        );
+        (position, zoom)
+    }
+
+    /// Parameters of frame `index`.
+    pub fn frame(&self, index: usize) -> Mandelbrot {
+        let t = if self.frame_count() > 1 {
+            index as f32 / (self.frame_count() - 1) as f32
+        } else {
+            0.0
+        };
+        let (position, scale) = self.view_at(t);
+        Mandelbrot {
+            position,
+            scale,
+            ..self.start.clone()
+        }
+    }
+
+    /// Renders all frames in order and passes each one with its parameters to
+    /// `sink` as RGBA8. Stops at the first error returned by `sink`.
+    pub fn render<E>(
+        &self,
+        mode: FrameMode,
+        mut sink: impl FnMut(usize, &Mandelbrot, &[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let frames: Vec<Mandelbrot> = (0..self.frame_count()).map(|index| self.frame(index)).collect();
+        let (width, height) = (self.start.width, self.start.height);
+        let mut pixels = vec![0u8; width * height * 4];
+        match mode {
+            FrameMode::Exact => {
+                for (index, frame) in frames.iter().enumerate() {
+                    frame.render(&mut pixels);
+                    sink(index, frame, &pixels)?;
+                }
+            }
+            FrameMode::Keyframes => {
+                let mut linear = vec![0f32; width * height * 4];
+                let mut index = 0;
+                while index < frames.len() {
+                    let octave = Self::octave(&frames[index]);
+                    let end = frames[index..]
+                        .iter()
+                        .position(|frame| Self::octave(frame) != octave)
+                        .map_or(frames.len(), |offset| index + offset);
+                    let keyframe = Keyframe::render(self, &frames[index..end]);
+                    for (offset, frame) in frames[index..end].iter().enumerate() {
+                        keyframe.resample(frame, &mut linear);
+                        frame.tone_mapping.to_rgba8(&linear, &mut pixels);
+                        sink(index + offset, frame, &pixels)?;
+                    }
+                    index = end;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn octave(frame: &Mandelbrot) -> i32 {
+        frame.scale.log2().floor() as i32
+    }
+}
+
+/// Linear-light render covering the views of several frames with square pixels.
+/// All frames share the orientation of the animation, so the bounds are taken
+/// in view space, where the views are axis-aligned.
+struct Keyframe {
+    width: usize,
+    height: usize,
+    mapping: PixelMapping,
+    pixels: Vec<f32>,
+}
+
+impl Keyframe {
+    fn render(animation: &ZoomAnimation, frames: &[Mandelbrot]) -> Self {
+        let start = &animation.start;
+        let view_rect = |frame: &Mandelbrot| {
+            let (x, y) = start.transform.invert(frame.position.x, frame.position.y);
+            let extent = frame.extent();
+            Rectangle::new(
+                Vector::new(x - extent.x, y - extent.y),
+                Vector::new(x + extent.x, y + extent.y),
+            )
+        };
+        let mut bounds = view_rect(&frames[0]);
+        for frame in &frames[1..] {
+            let rect = view_rect(frame);
+            bounds.start.x = bounds.start.x.min(rect.start.x);
+            bounds.start.y = bounds.start.y.min(rect.start.y);
+            bounds.end.x = bounds.end.x.max(rect.end.x);
+            bounds.end.y = bounds.end.y.max(rect.end.y);
+        }
+        let finest = frames
+            .iter()
+            .map(|frame| 2.0 * frame.extent().x / start.width as f32)
+            .fold(f32::INFINITY, f32::min);
+        // Keep at least one keyframe pixel per frame pixel unless the keyframe would get too large.
+        let max_width = start.width as f32 * animation.max_keyframe_scale;
+        let max_height = start.height as f32 * animation.max_keyframe_scale;
+        let pixel_size = finest.max(bounds.width() / max_width).max(bounds.height() / max_height);
+        let width = (bounds.width() / pixel_size).ceil() as usize + 1;
+        let height = (bounds.height() / pixel_size).ceil() as usize + 1;
+
+        let mut keyframe = start.clone();
+        keyframe.set_resolution(width, height);
+        // Square pixels, whatever the policy of the frames.
+        keyframe.aspect = Aspect::Fit;
+        let extent = Vector::new(width as f32 * pixel_size / 2.0, height as f32 * pixel_size / 2.0);
+        keyframe.fit_extent(&extent);
+        let (x, y) = start
+            .transform
+            .apply(bounds.start.x + extent.x, bounds.start.y + extent.y);
+        keyframe.position = Vector::new(x, y);
+        let mut pixels = vec![0f32; width * height * 4];
+        keyframe.render_hdr(&mut pixels);
+        Self {
+            width,
+            height,
+            mapping: keyframe.mapping(),
+            pixels,
+        }
+    }
+
+    /// Bilinear resampling of the keyframe into the view of `frame`.
+    fn resample(&self, frame: &Mandelbrot, out: &mut [f32]) {
+        let mapping = frame.mapping();
+        out.par_chunks_exact_mut(4).enumerate().for_each(|(index, pixel)| {
+            let c = mapping.map((index % frame.width) as f32, (index / frame.width) as f32);
+            let (u, v) = self.mapping.inverse(c);
+            let u = u.clamp(0.0, (self.width - 1) as f32);
+            let v = v.clamp(0.0, (self.height - 1) as f32);
+            let (x0, y0) = (u as usize, v as usize);
+            let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+            let (fx, fy) = (u - x0 as f32, v - y0 as f32);
+            for (channel, value) in pixel.iter_mut().enumerate() {
+                let texel = |x: usize, y: usize| self.pixels[(y * self.width + x) * 4 + channel];
+                let top = texel(x0, y0) + (texel(x1, y0) - texel(x0, y0)) * fx;
+                let bottom = texel(x0, y1) + (texel(x1, y1) - texel(x0, y1)) * fx;
+                *value = top + (bottom - top) * fy;
+            }
+        });
+    }
+}
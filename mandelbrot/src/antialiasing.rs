@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+use super::hdr::hash;
+
+#[derive(Debug, Clone, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+pub enum SamplePattern {
+    Grid,
+    RotatedGrid,
+    Jittered,
+}
+
+/// Supersampling of each pixel with `samples`×`samples` points. In adaptive
+/// mode only pixels whose neighbours differ by more than `threshold` in their
+/// coloring value are supersampled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Antialiasing {
+    pub samples: usize,
+    pub pattern: SamplePattern,
+    pub adaptive: bool,
+    pub threshold: f32,
+}
+
+impl Antialiasing {
+    pub fn enabled(&self) -> bool {
+        self.samples > 1
+    }
+
+    /// Sample offsets in pixels relative to the pixel position, all within [-0.5, 0.5).
+    pub fn offsets(&self, pixel: usize) -> Vec<(f32, f32)> {
+        let n = self.samples.max(1);
+        let step = 1.0 / n as f32;
+        // The rotated grid uses the classic atan(1/2) angle, which gives every
+        // sample a distinct row and column.
+        let (sin, cos) = f32::atan(0.5).sin_cos();
+        (0..n * n)
+            .map(|index| {
+                let (i, j) = ((index % n) as f32, (index / n) as f32);
+                match self.pattern {
+                    SamplePattern::Grid => ((i + 0.5) * step - 0.5, (j + 0.5) * step - 0.5),
+                    SamplePattern::RotatedGrid => {
+                        let (x, y) = ((i + 0.5) * step - 0.5, (j + 0.5) * step - 0.5);
+                        let (x, y) = (x * cos - y * sin, x * sin + y * cos);
+                        ((x + 0.5).rem_euclid(1.0) - 0.5, (y + 0.5).rem_euclid(1.0) - 0.5)
+                    }
+                    SamplePattern::Jittered => {
+                        let seed = (pixel * n * n + index) as u32;
+                        (
+                            (i + hash(seed.wrapping_mul(2))) * step - 0.5,
+                            (j + hash(seed.wrapping_mul(2).wrapping_add(1))) * step - 0.5,
+                        )
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for Antialiasing {
+    fn default() -> Self {
+        Self {
+            samples: 1,
+            pattern: SamplePattern::RotatedGrid,
+            adaptive: true,
+            threshold: 0.01,
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+use super::antialiasing::Antialiasing;
+use super::history::ViewState;
+use super::mandelbrot::Mandelbrot;
+use super::vector::Vector;
+
+/// How the autopilot measures the detail of a region.
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum Detail {
+    /// Share of neighboring pixels with different iteration counts.
+    Boundary,
+    /// Variance of the logarithmic distance estimate of the escaping pixels.
+    Variance,
+}
+
+/// Picks a descent path by zooming into the region of the frame with the
+/// most detail, over and over. Among regions that come close to the best
+/// one the choice is random, so every seed takes a different path.
+#[derive(Debug, Clone)]
+pub struct Autopilot {
+    pub detail: Detail,
+    /// Factor the scale is multiplied with at every step.
+    pub zoom_factor: f32,
+    /// The frame is split into `grid`×`grid` regions to choose from.
+    pub grid: usize,
+    /// Width of the render that measures the detail, the height follows the view.
+    pub probe_width: usize,
+    /// Regions scoring at least this share of the best one are candidates.
+    pub tolerance: f32,
+    /// The descent ends at this scale. Views much deeper than 1e-5 exceed the
+    /// f32 precision of the renderer.
+    pub target_scale: f32,
+    state: u64,
+}
+
+impl Autopilot {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            detail: Detail::Boundary,
+            zoom_factor: 0.5,
+            grid: 4,
+            probe_width: 192,
+            tolerance: 0.7,
+            target_scale: 1e-5,
+            state: seed,
+        }
+    }
+
+    /// Next view down from the view of `mandelbrot`. `None` once the target
+    /// scale is reached or the frame shows no detail at all.
+    pub fn step(&mut self, mandelbrot: &Mandelbrot) -> Option<ViewState> {
+        if mandelbrot.scale * self.zoom_factor < self.target_scale {
+            return None;
+        }
+        let grid = self.grid.max(1);
+        let width = self.probe_width.max(grid);
+        let height = (width * mandelbrot.height / mandelbrot.width.max(1)).max(grid);
+        let mut probe = mandelbrot.clone();
+        probe.set_resolution(width, height);
+        probe.antialiasing = Antialiasing::default();
+
+        let scores = self.scores(&probe);
+        let best = scores.iter().copied().fold(0.0, f32::max);
+        if best <= 0.0 {
+            return None;
+        }
+        let candidates: Vec<usize> = (0..scores.len())
+            .filter(|&cell| scores[cell] >= best * self.tolerance)
+            .collect();
+        let cell = candidates[(self.next_random() % candidates.len() as u64) as usize];
+        let x = ((cell % grid) as f32 + 0.5) * width as f32 / grid as f32;
+        let y = ((cell / grid) as f32 + 0.5) * height as f32 / grid as f32;
+        let center = probe.mapping().map(x, y);
+        Some(ViewState {
+            position: Vector::new(center.re, center.im),
+            scale: mandelbrot.scale * self.zoom_factor,
+            transform: mandelbrot.transform.clone(),
+            aspect: mandelbrot.aspect,
+            max_iterations: Some(mandelbrot.max_iterations),
+        })
+    }
+
+    /// Views from `start` down to the target scale, at most `max_steps` below it.
+    pub fn tour(&mut self, start: &Mandelbrot, max_steps: usize) -> Vec<ViewState> {
+        let mut mandelbrot = start.clone();
+        let mut trail = vec![ViewState::of(&mandelbrot)];
+        for _ in 0..max_steps {
+            let Some(view) = self.step(&mandelbrot) else { break };
+            view.apply(&mut mandelbrot);
+            trail.push(view);
+        }
+        trail
+    }
+
+    /// Detail score of every region, row by row.
+    fn scores(&self, probe: &Mandelbrot) -> Vec<f32> {
+        let grid = self.grid.max(1);
+        let (width, height) = (probe.width, probe.height);
+        let cell = |index: usize| (index / width * grid / height) * grid + index % width * grid / width;
+        let values = match self.detail {
+            Detail::Boundary => boundary_values(probe),
+            Detail::Variance => distance_values(probe),
+        };
+        let mut sums = vec![0.0f64; grid * grid];
+        let mut squares = vec![0.0f64; grid * grid];
+        let mut counts = vec![0usize; grid * grid];
+        for (index, value) in values.into_iter().enumerate() {
+            if let Some(value) = value {
+                let cell = cell(index);
+                sums[cell] += value;
+                squares[cell] += value * value;
+                counts[cell] += 1;
+            }
+        }
+        (0..grid * grid)
+            .map(|index| {
+                let count = counts[index].max(1) as f64;
+                let mean = sums[index] / count;
+                let score = match self.detail {
+                    Detail::Boundary => mean,
+                    Detail::Variance => squares[index] / count - mean * mean,
+                };
+                score as f32
+            })
+            .collect()
+    }
+
+    /// SplitMix64, small and good enough to pick regions.
+    fn next_random(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Share of the right and lower neighbors of every pixel with a different iteration count.
+fn boundary_values(probe: &Mandelbrot) -> Vec<Option<f64>> {
+    let field = probe.compute_field();
+    let (width, height) = (field.width, field.height);
+    (0..width * height)
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            let differs = |other: usize| field.samples[other].iterations != field.samples[index].iterations;
+            let edges =
+                (x + 1 < width && differs(index + 1)) as usize + (y + 1 < height && differs(index + width)) as usize;
+            Some(edges as f64 / 2.0)
+        })
+        .collect()
+}
+
+/// Logarithm of the distance estimate of every escaping pixel, in pixels.
+fn distance_values(probe: &Mandelbrot) -> Vec<Option<f64>> {
+    let mapping = probe.mapping();
+    let pixel_size = mapping.step_x.norm() as f64;
+    (0..probe.width * probe.height)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = ((index % probe.width) as f32, (index / probe.width) as f32);
+            let distance = probe.distance_estimate(&mapping.map(x, y))? as f64;
+            (distance > 0.0).then(|| (distance / pixel_size).ln())
+        })
+        .collect()
+}
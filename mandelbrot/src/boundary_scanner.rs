@@ -1,10 +1,8 @@
 use std::collections::VecDeque;
 
-use num::complex::Complex32;
-
 use crate::{
-    mandelbrot::{Mandelbrot, rect_from_position},
-    range::Range,
+    mandelbrot::{Mandelbrot, Sample},
+    transform::PixelMapping,
 };
 
 struct BitVec {
@@ -12,6 +10,7 @@ struct BitVec {
 }
 
 impl BitVec {
+    #[allow(clippy::manual_div_ceil)]
     fn new(size: usize) -> Self {
         Self {
             data: vec![0; (size + 63) / 64],
@@ -35,11 +34,8 @@ pub struct BoundaryScanner<'a> {
     pub(crate) mandelbrot: &'a Mandelbrot,
     pub(crate) start: usize,
     pub(crate) end: usize,
-    pub(crate) width_range: Range,
-    pub(crate) height_range: Range,
-    pub(crate) real_range: Range,
-    pub(crate) imaginary_range: Range,
-    pub(crate) data: Vec<u32>,
+    pub(crate) mapping: PixelMapping,
+    pub(crate) data: Vec<Sample>,
     queued: BitVec,
     loaded: BitVec,
     pub(crate) queue: VecDeque<usize>,
@@ -52,17 +48,12 @@ impl<'a> BoundaryScanner<'a> {
         let size = width * height;
         let queue_size = (width + height) * 2;
 
-        let rect = rect_from_position(&mandelbrot.position, &mandelbrot.zoom);
-
         Self {
             mandelbrot,
             start,
             end,
-            width_range: Range::new(0.0, mandelbrot.width as f32),
-            height_range: Range::new(0.0, mandelbrot.height as f32),
-            real_range: Range::new(rect.start.x, rect.end.x),
-            imaginary_range: Range::new(rect.start.y, rect.end.y),
-            data: vec![0; size],
+            mapping: mandelbrot.mapping(),
+            data: vec![Sample::default(); size],
             queued: BitVec::new(size),
             loaded: BitVec::new(size),
             queue: VecDeque::with_capacity(queue_size),
@@ -86,21 +77,16 @@ impl<'a> BoundaryScanner<'a> {
     fn load(&mut self, index: usize) -> u32 {
         let local_index = self.local_index(index);
         if self.loaded.get(local_index) {
-            return self.data[local_index];
+            return self.data[local_index].iterations;
         }
 
         let x = (index % self.mandelbrot.width) as f32;
         let y = (index / self.mandelbrot.width) as f32;
 
-        let c = Complex32::new(
-            Range::scale(&self.width_range, x, &self.real_range),
-            Range::scale(&self.height_range, y, &self.imaginary_range),
-        );
-
-        let (_, result) = self.mandelbrot.iterate(&c);
+        let sample = self.mandelbrot.sample(&self.mapping.map(x, y));
         self.loaded.set(local_index);
-        self.data[local_index] = result as u32;
-        result as u32
+        self.data[local_index] = sample;
+        sample.iterations
     }
 
     fn scan(&mut self, index: usize) {
@@ -142,7 +128,7 @@ impl<'a> BoundaryScanner<'a> {
         }
     }
 
-    pub fn run(&mut self) -> &[u32] {
+    pub fn run(&mut self) -> &[Sample] {
         let width = self.mandelbrot.width;
 
         for y in self.start..self.end {
@@ -0,0 +1,223 @@
+use std::fmt;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+#[derive(Debug, Clone, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+pub enum ToneMapping {
+    None,
+    Reinhard,
+    ACES,
+}
+
+/// Maps linear-light RGBA to display values. `exposure` is in stops and
+/// `gamma` is applied on top of the sRGB transfer curve.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToneMapper {
+    pub operator: ToneMapping,
+    pub exposure: f32,
+    pub gamma: f32,
+    pub dither: bool,
+}
+
+impl ToneMapper {
+    pub fn map(&self, value: f32) -> f32 {
+        let value = value * self.exposure.exp2();
+        let value = match self.operator {
+            ToneMapping::None => value,
+            ToneMapping::Reinhard => value / (1.0 + value),
+            // Narkowicz's fit of the ACES filmic curve.
+            ToneMapping::ACES => (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14),
+        };
+        let value = value.clamp(0.0, 1.0);
+        if self.gamma == 1.0 {
+            value
+        } else {
+            value.powf(1.0 / self.gamma)
+        }
+    }
+
+    /// Tone maps and sRGB encodes a linear pixel. Alpha is passed through.
+    pub fn encode(&self, pixel: &[f32; 4]) -> [f32; 4] {
+        [
+            to_srgb(self.map(pixel[0])),
+            to_srgb(self.map(pixel[1])),
+            to_srgb(self.map(pixel[2])),
+            pixel[3].clamp(0.0, 1.0),
+        ]
+    }
+
+    /// 8-bit encoding of a linear pixel, `index` seeds the dither noise.
+    pub fn encode_rgba8(&self, pixel: &[f32; 4], index: usize) -> [u8; 4] {
+        let pixel = self.encode(pixel);
+        let mut encoded = [0u8; 4];
+        for channel in 0..4 {
+            let noise = if self.dither && channel < 3 {
+                triangular_noise(index * 4 + channel)
+            } else {
+                0.0
+            };
+            encoded[channel] = (pixel[channel] * 255.0 + 0.5 + noise).clamp(0.0, 255.0) as u8;
+        }
+        encoded
+    }
+
+    pub fn to_rgba8(&self, pixels: &[f32], out: &mut [u8]) {
+        out.par_chunks_exact_mut(4)
+            .zip(pixels.par_chunks_exact(4))
+            .enumerate()
+            .for_each(|(index, (out, pixel))| {
+                out.copy_from_slice(&self.encode_rgba8(&[pixel[0], pixel[1], pixel[2], pixel[3]], index));
+            });
+    }
+
+    /// 16 bits per channel are enough to avoid visible banding in smooth gradients without dithering.
+    pub fn to_rgba16(&self, pixels: &[f32]) -> Vec<u16> {
+        pixels
+            .par_chunks_exact(4)
+            .flat_map_iter(|pixel| {
+                self.encode(&[pixel[0], pixel[1], pixel[2], pixel[3]])
+                    .map(|value| (value * 65535.0 + 0.5) as u16)
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "image")]
+    pub fn save_png16(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        width: usize,
+        height: usize,
+        pixels: &[f32],
+    ) -> image::ImageResult<()> {
+        let buffer =
+            image::ImageBuffer::<image::Rgba<u16>, _>::from_raw(width as u32, height as u32, self.to_rgba16(pixels))
+                .ok_or_else(|| {
+                    image::ImageError::Parameter(image::error::ParameterError::from_kind(
+                        image::error::ParameterErrorKind::DimensionMismatch,
+                    ))
+                })?;
+        buffer.save(path)
+    }
+}
+
+impl Default for ToneMapper {
+    fn default() -> Self {
+        Self {
+            operator: ToneMapping::None,
+            exposure: 0.0,
+            gamma: 1.0,
+            dither: false,
+        }
+    }
+}
+
+/// A frame passed to an `Accumulator` of another size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameSizeError {
+    /// Values of one frame of the accumulator, 4 per pixel.
+    pub expected: usize,
+    pub actual: usize,
+}
+
+impl fmt::Display for FrameSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "frame has {} values, expected {}", self.actual, self.expected)
+    }
+}
+
+impl std::error::Error for FrameSizeError {}
+
+/// Running sum of linear RGBA frames or individual splats, e.g. for
+/// progressive rendering or Buddhabrot style hit counting.
+#[derive(Debug, Clone)]
+pub struct Accumulator {
+    pub width: usize,
+    pub height: usize,
+    data: Vec<f32>,
+    frames: u32,
+}
+
+impl Accumulator {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0.0; width * height * 4],
+            frames: 0,
+        }
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn clear(&mut self) {
+        self.data.fill(0.0);
+        self.frames = 0;
+    }
+
+    pub fn add_frame(&mut self, pixels: &[f32]) -> Result<(), FrameSizeError> {
+        if pixels.len() != self.data.len() {
+            return Err(FrameSizeError {
+                expected: self.data.len(),
+                actual: pixels.len(),
+            });
+        }
+        self.data
+            .par_iter_mut()
+            .zip(pixels.par_iter())
+            .for_each(|(sum, value)| *sum += value);
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Adds `color` weighted by `weight` to a single pixel. Out of bounds splats are ignored.
+    pub fn splat(&mut self, x: usize, y: usize, color: [f32; 3], weight: f32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let index = (y * self.width + x) * 4;
+        for (sum, value) in self.data[index..index + 3].iter_mut().zip(color) {
+            *sum += value * weight;
+        }
+        self.data[index + 3] += weight;
+    }
+
+    /// Average over the added frames, splats are scaled the same way and
+    /// are usually brought into range with the tone mapper's exposure.
+    pub fn resolve(&self, pixels: &mut [f32]) {
+        let scale = 1.0 / self.frames.max(1) as f32;
+        pixels
+            .par_iter_mut()
+            .zip(self.data.par_iter())
+            .for_each(|(pixel, sum)| *pixel = sum * scale);
+    }
+}
+
+fn to_srgb(value: f32) -> f32 {
+    if value >= 0.0031308 {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    } else {
+        12.92 * value
+    }
+}
+
+/// Deterministic value in [0, 1) from an integer hash, so dithered and
+/// jittered renders are reproducible.
+pub(crate) fn hash(mut x: u32) -> f32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+    (x >> 8) as f32 / (1 << 24) as f32
+}
+
+/// Triangular distributed noise in (-1, 1).
+fn triangular_noise(seed: usize) -> f32 {
+    let seed = seed as u32;
+    hash(seed.wrapping_mul(2)) + hash(seed.wrapping_mul(2).wrapping_add(1)) - 1.0
+}
@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::mandelbrot::{Aspect, Mandelbrot};
+use super::parameters::{ParameterError, ParameterFile, ParameterFormat, VERSION};
+use super::transform::ViewTransform;
+use super::vector::Vector;
+
+/// The part of the parameters that navigating changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewState {
+    pub position: Vector,
+    pub scale: f32,
+    #[serde(default)]
+    pub transform: ViewTransform,
+    #[serde(default)]
+    pub aspect: Aspect,
+    /// Deep views need more iterations. Left unchanged by views that do not store it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_iterations: Option<usize>,
+}
+
+impl ViewState {
+    pub fn of(mandelbrot: &Mandelbrot) -> Self {
+        Self {
+            position: mandelbrot.position.clone(),
+            scale: mandelbrot.scale,
+            transform: mandelbrot.transform.clone(),
+            aspect: mandelbrot.aspect,
+            max_iterations: Some(mandelbrot.max_iterations),
+        }
+    }
+
+    pub fn apply(&self, mandelbrot: &mut Mandelbrot) {
+        mandelbrot.position = self.position.clone();
+        mandelbrot.scale = self.scale;
+        mandelbrot.transform = self.transform.clone();
+        mandelbrot.aspect = self.aspect;
+        if let Some(max_iterations) = self.max_iterations {
+            mandelbrot.max_iterations = max_iterations;
+        }
+    }
+}
+
+/// Bounded undo/redo stack of views. Frontends record the view before every
+/// navigation step, undoing returns to it.
+#[derive(Debug, Clone)]
+pub struct History {
+    undo: VecDeque<ViewState>,
+    redo: Vec<ViewState>,
+    capacity: usize,
+}
+
+impl History {
+    pub const DEFAULT_CAPACITY: usize = 100;
+
+    /// Keeps at most `capacity` views to undo to, dropping the oldest.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Remembers `previous`, the view before a navigation step, and forgets
+    /// the views that were undone.
+    pub fn record(&mut self, previous: ViewState) {
+        self.redo.clear();
+        if self.undo.back() != Some(&previous) {
+            self.push_undo(previous);
+        }
+    }
+
+    /// Returns the view to go back to, `current` becomes available to `redo`.
+    pub fn undo(&mut self, current: ViewState) -> Option<ViewState> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// Returns the view that was last undone, `current` becomes available to `undo`.
+    pub fn redo(&mut self, current: ViewState) -> Option<ViewState> {
+        let next = self.redo.pop()?;
+        self.push_undo(current);
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    fn push_undo(&mut self, state: ViewState) {
+        self.undo.push_back(state);
+        while self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    #[serde(flatten)]
+    pub view: ViewState,
+}
+
+/// Named views, stored as JSON or TOML.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmarks {
+    pub version: u32,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    pub fn get(&self, name: &str) -> Option<&Bookmark> {
+        self.bookmarks.iter().find(|bookmark| bookmark.name == name)
+    }
+
+    /// Adds a bookmark, replacing one of the same name in place. Returns its index.
+    pub fn insert(&mut self, name: &str, view: ViewState) -> usize {
+        let bookmark = Bookmark {
+            name: name.to_string(),
+            view,
+        };
+        match self.bookmarks.iter().position(|bookmark| bookmark.name == name) {
+            Some(index) => {
+                self.bookmarks[index] = bookmark;
+                index
+            }
+            None => {
+                self.bookmarks.push(bookmark);
+                self.bookmarks.len() - 1
+            }
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Bookmark> {
+        let index = self.bookmarks.iter().position(|bookmark| bookmark.name == name)?;
+        Some(self.bookmarks.remove(index))
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, ParameterError> {
+        let bookmarks: Self = serde_json::from_str(source)?;
+        ParameterFile::check_version(bookmarks.version)?;
+        Ok(bookmarks)
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, ParameterError> {
+        let bookmarks: Self = toml::from_str(source).map_err(|error| ParameterError::Toml(error.to_string()))?;
+        ParameterFile::check_version(bookmarks.version)?;
+        Ok(bookmarks)
+    }
+
+    pub fn to_json(&self) -> Result<String, ParameterError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_toml(&self) -> Result<String, ParameterError> {
+        toml::to_string_pretty(self).map_err(|error| ParameterError::Toml(error.to_string()))
+    }
+
+    /// Reads the bookmarks at `path`, a missing file has none.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ParameterError> {
+        let path = path.as_ref();
+        let format = Self::format(path)?;
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(error.into()),
+        };
+        match format {
+            ParameterFormat::Json => Self::from_json(&source),
+            _ => Self::from_toml(&source),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ParameterError> {
+        let path = path.as_ref();
+        let source = match Self::format(path)? {
+            ParameterFormat::Json => self.to_json()?,
+            _ => self.to_toml()?,
+        };
+        Ok(std::fs::write(path, source)?)
+    }
+
+    fn format(path: &Path) -> Result<ParameterFormat, ParameterError> {
+        match ParameterFormat::from_path(path) {
+            Some(format @ (ParameterFormat::Json | ParameterFormat::Toml)) => Ok(format),
+            _ => Err(ParameterError::UnknownFormat(path.display().to_string())),
+        }
+    }
+}
+
+impl Default for Bookmarks {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            bookmarks: Vec::new(),
+        }
+    }
+}
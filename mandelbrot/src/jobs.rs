@@ -0,0 +1,139 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::mandelbrot::Mandelbrot;
+use super::parameters::{ParameterError, ParameterFile, ParameterFormat, VERSION};
+
+/// One image of a render queue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Relative paths are resolved against the directory of the job file.
+    pub output: PathBuf,
+    /// Parameter file replacing the base parameters of the job file.
+    #[serde(default)]
+    pub parameters: Option<PathBuf>,
+    /// Jobs with a higher priority run first.
+    #[serde(default)]
+    pub priority: i32,
+    /// Fields in parameter file layout, merged over the parameters of the job.
+    #[serde(default)]
+    pub overrides: serde_json::Value,
+}
+
+/// Render queue: shared base parameters and the jobs that derive from them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobFile {
+    pub version: u32,
+    #[serde(default)]
+    pub base: Mandelbrot,
+    pub jobs: Vec<Job>,
+}
+
+impl Job {
+    /// Parameters of the job, `directory` is the directory of the job file.
+    pub fn mandelbrot(&self, base: &Mandelbrot, directory: &Path) -> Result<Mandelbrot, ParameterError> {
+        let mandelbrot = match &self.parameters {
+            Some(path) => Mandelbrot::load(directory.join(path))?,
+            None => base.clone(),
+        };
+        if self.overrides.is_null() {
+            return Ok(mandelbrot);
+        }
+        if !self.overrides.is_object() {
+            return Err(ParameterError::Invalid("overrides must be a table".to_string()));
+        }
+        let mut value = serde_json::to_value(&mandelbrot)?;
+        merge(&mut value, &self.overrides);
+        serde_json::from_value::<Mandelbrot>(value)?.validated()
+    }
+
+    pub fn output_path(&self, directory: &Path) -> PathBuf {
+        directory.join(&self.output)
+    }
+
+    pub fn label(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.output.display().to_string())
+    }
+}
+
+/// Recursively replaces the fields of `target` that `overrides` sets.
+fn merge(target: &mut serde_json::Value, overrides: &serde_json::Value) {
+    match (target, overrides) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match target.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, overrides) => *target = overrides.clone(),
+    }
+}
+
+impl JobFile {
+    pub fn new(base: Mandelbrot, jobs: Vec<Job>) -> Self {
+        Self {
+            version: VERSION,
+            base,
+            jobs,
+        }
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, ParameterError> {
+        serde_json::from_str::<Self>(source)?.validated()
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, ParameterError> {
+        toml::from_str::<Self>(source)
+            .map_err(|error| ParameterError::Toml(error.to_string()))?
+            .validated()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ParameterError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        match ParameterFormat::from_path(path) {
+            Some(ParameterFormat::Json) => Self::from_json(&source),
+            Some(ParameterFormat::Toml) => Self::from_toml(&source),
+            _ => Err(ParameterError::UnknownFormat(path.display().to_string())),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, ParameterError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_toml(&self) -> Result<String, ParameterError> {
+        toml::to_string_pretty(self).map_err(|error| ParameterError::Toml(error.to_string()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ParameterError> {
+        let path = path.as_ref();
+        let source = match ParameterFormat::from_path(path) {
+            Some(ParameterFormat::Json) => self.to_json()?,
+            Some(ParameterFormat::Toml) => self.to_toml()?,
+            _ => return Err(ParameterError::UnknownFormat(path.display().to_string())),
+        };
+        Ok(std::fs::write(path, source)?)
+    }
+
+    /// Jobs in the order they should run, by descending priority and
+    /// otherwise in file order.
+    pub fn queue(&self) -> Vec<&Job> {
+        let mut queue: Vec<&Job> = self.jobs.iter().collect();
+        queue.sort_by_key(|job| std::cmp::Reverse(job.priority));
+        queue
+    }
+
+    fn validated(mut self) -> Result<Self, ParameterError> {
+        ParameterFile::check_version(self.version)?;
+        self.base = self.base.validated()?;
+        Ok(self)
+    }
+}
@@ -0,0 +1,259 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+use super::mandelbrot::Mandelbrot;
+use super::parameters::{ParameterError, ParameterFile, ParameterFormat, VERSION};
+use super::vector::Vector;
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum Curve {
+    /// Holds the previous value until the next keyframe.
+    Step,
+    Linear,
+    Smoothstep,
+    CatmullRom,
+    /// Linear in log space, which zooms at a constant rate. Falls back to
+    /// linear when a value is not positive.
+    Log,
+}
+
+impl Curve {
+    /// Interpolates between `p1` and `p2`, `p0` and `p3` are their outer neighbours.
+    pub fn interpolate(&self, p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        match self {
+            Curve::Step => p1,
+            Curve::Linear => lerp(p1, p2, t),
+            Curve::Smoothstep => lerp(p1, p2, t * t * (3.0 - 2.0 * t)),
+            Curve::CatmullRom => {
+                let (t2, t3) = (t * t, t * t * t);
+                0.5 * (2.0 * p1
+                    + (p2 - p0) * t
+                    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+                    + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+            }
+            Curve::Log if p1 > 0.0 && p2 > 0.0 => lerp(p1.ln(), p2.ln(), t).exp(),
+            Curve::Log => lerp(p1, p2, t),
+        }
+    }
+}
+
+/// Interpolation curve of every animatable parameter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Curves {
+    pub center: Curve,
+    pub zoom: Curve,
+    pub rotation: Curve,
+    pub iterations: Curve,
+    pub julia: Curve,
+    pub palette_offset: Curve,
+    pub palette_repeat: Curve,
+    pub exponent: Curve,
+}
+
+impl Default for Curves {
+    fn default() -> Self {
+        Self {
+            center: Curve::CatmullRom,
+            zoom: Curve::Log,
+            rotation: Curve::Linear,
+            iterations: Curve::Log,
+            julia: Curve::CatmullRom,
+            palette_offset: Curve::Linear,
+            palette_repeat: Curve::Linear,
+            exponent: Curve::Linear,
+        }
+    }
+}
+
+/// Parameter values at `time` seconds. Parameters that are left out are
+/// interpolated between the surrounding keyframes that set them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keyframe {
+    pub time: f32,
+    pub center: Option<Vector>,
+    /// Scale of the view, the aspect policy of the base view applies.
+    pub zoom: Option<f32>,
+    /// Counterclockwise view rotation in degrees, interpolated without wrapping.
+    pub rotation: Option<f32>,
+    pub iterations: Option<f32>,
+    pub julia: Option<Vector>,
+    pub palette_offset: Option<f32>,
+    pub palette_repeat: Option<f32>,
+    pub exponent: Option<f32>,
+}
+
+/// Camera path: a base parameter set animated by keyframes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyframeFile {
+    pub version: u32,
+    #[serde(default = "default_frame_rate")]
+    pub frame_rate: f32,
+    #[serde(default)]
+    pub base: Mandelbrot,
+    #[serde(default)]
+    pub curves: Curves,
+    pub keyframes: Vec<Keyframe>,
+}
+
+fn default_frame_rate() -> f32 {
+    30.0
+}
+
+impl KeyframeFile {
+    pub fn new(base: Mandelbrot, keyframes: Vec<Keyframe>) -> Self {
+        Self {
+            version: VERSION,
+            frame_rate: default_frame_rate(),
+            base,
+            curves: Curves::default(),
+            keyframes,
+        }
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, ParameterError> {
+        serde_json::from_str::<Self>(source)?.validated()
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, ParameterError> {
+        toml::from_str::<Self>(source)
+            .map_err(|error| ParameterError::Toml(error.to_string()))?
+            .validated()
+    }
+
+    pub fn to_json(&self) -> Result<String, ParameterError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn to_toml(&self) -> Result<String, ParameterError> {
+        toml::to_string_pretty(self).map_err(|error| ParameterError::Toml(error.to_string()))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ParameterError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        match ParameterFormat::from_path(path) {
+            Some(ParameterFormat::Json) => Self::from_json(&source),
+            Some(ParameterFormat::Toml) => Self::from_toml(&source),
+            _ => Err(ParameterError::UnknownFormat(path.display().to_string())),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ParameterError> {
+        let path = path.as_ref();
+        let source = match ParameterFormat::from_path(path) {
+            Some(ParameterFormat::Json) => self.to_json()?,
+            Some(ParameterFormat::Toml) => self.to_toml()?,
+            _ => return Err(ParameterError::UnknownFormat(path.display().to_string())),
+        };
+        Ok(std::fs::write(path, source)?)
+    }
+
+    fn validated(mut self) -> Result<Self, ParameterError> {
+        ParameterFile::check_version(self.version)?;
+        if self.keyframes.is_empty() {
+            return Err(ParameterError::Invalid("at least one keyframe is required".to_string()));
+        }
+        if self.frame_rate.is_nan() || self.frame_rate <= 0.0 {
+            return Err(ParameterError::Invalid("frame rate must be positive".to_string()));
+        }
+        self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self.base = self.base.validated()?;
+        Ok(self)
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        (self.duration() * self.frame_rate).floor() as usize + 1
+    }
+
+    pub fn frame(&self, index: usize) -> Mandelbrot {
+        self.state_at(index as f32 / self.frame_rate)
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = Mandelbrot> + '_ {
+        (0..self.frame_count()).map(|index| self.frame(index))
+    }
+
+    /// Evaluates every parameter track at `time` on top of the base parameters.
+    pub fn state_at(&self, time: f32) -> Mandelbrot {
+        let mut mandelbrot = self.base.clone();
+        let curves = &self.curves;
+        if let Some([x, y]) = self.track(time, curves.center, |keyframe| {
+            keyframe.center.as_ref().map(|center| [center.x, center.y])
+        }) {
+            mandelbrot.position = Vector::new(x, y);
+        }
+        if let Some([zoom]) = self.track(time, curves.zoom, |keyframe| keyframe.zoom.map(|zoom| [zoom])) {
+            mandelbrot.scale = zoom;
+        }
+        if let Some([rotation]) = self.track(time, curves.rotation, |keyframe| {
+            keyframe.rotation.map(|rotation| [rotation])
+        }) {
+            mandelbrot.transform.rotation = rotation;
+        }
+        if let Some([iterations]) = self.track(time, curves.iterations, |keyframe| {
+            keyframe.iterations.map(|iterations| [iterations])
+        }) {
+            mandelbrot.max_iterations = (iterations.round() as usize).max(1);
+        }
+        if let Some([x, y]) = self.track(time, curves.julia, |keyframe| {
+            keyframe.julia.as_ref().map(|julia| [julia.x, julia.y])
+        }) {
+            mandelbrot.julia = Some(Vector::new(x, y));
+        }
+        if let Some([offset]) = self.track(time, curves.palette_offset, |keyframe| {
+            keyframe.palette_offset.map(|offset| [offset])
+        }) {
+            mandelbrot.palette_offset = offset;
+        }
+        if let Some([repeat]) = self.track(time, curves.palette_repeat, |keyframe| {
+            keyframe.palette_repeat.map(|repeat| [repeat])
+        }) {
+            mandelbrot.palette_repeat = repeat;
+        }
+        if let Some([exponent]) = self.track(time, curves.exponent, |keyframe| {
+            keyframe.exponent.map(|exponent| [exponent])
+        }) {
+            mandelbrot.exponent = exponent;
+        }
+        mandelbrot
+    }
+
+    /// Value of one parameter at `time`, None if no keyframe sets it. Before
+    /// the first and after the last keyframe the value is held.
+    fn track<const N: usize>(
+        &self,
+        time: f32,
+        curve: Curve,
+        value: impl Fn(&Keyframe) -> Option<[f32; N]>,
+    ) -> Option<[f32; N]> {
+        let points: Vec<(f32, [f32; N])> = self
+            .keyframes
+            .iter()
+            .filter_map(|keyframe| value(keyframe).map(|value| (keyframe.time, value)))
+            .collect();
+        let next = points.iter().position(|(at, _)| *at > time);
+        let segment = match next {
+            None => return points.last().map(|(_, value)| *value),
+            Some(0) => return Some(points[0].1),
+            Some(next) => next - 1,
+        };
+        let (t1, p1) = points[segment];
+        let (t2, p2) = points[segment + 1];
+        let p0 = points[segment.saturating_sub(1)].1;
+        let p3 = points[(segment + 2).min(points.len() - 1)].1;
+        let t = (time - t1) / (t2 - t1);
+        Some(std::array::from_fn(|index| {
+            curve.interpolate(p0[index], p1[index], p2[index], p3[index], t)
+        }))
+    }
+}
@@ -1,7 +1,24 @@
 #![cfg_attr(all(target_arch = "aarch64", target_feature = "fcma"), feature(stdarch_neon_fcma))]
 
+pub mod analysis;
+pub mod animation;
+pub mod antialiasing;
+pub mod autopilot;
 pub mod boundary_scanner;
+pub mod hdr;
+pub mod history;
+pub mod jobs;
+pub mod keyframes;
+pub mod lighting;
 pub mod mandelbrot;
-pub mod range;
+pub mod misiurewicz;
+pub mod nucleus;
+pub mod orbit_trap;
+pub mod palette;
+pub mod parameters;
+pub mod poster;
+pub mod rays;
 pub mod rectangle;
+pub mod tiles;
+pub mod transform;
 pub mod vector;
@@ -0,0 +1,81 @@
+use num::complex::Complex32;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+#[derive(Debug, Clone, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+pub enum HeightField {
+    Smooth,
+    Distance,
+}
+
+/// Blinn-Phong lighting of the escape field. Angles are in degrees, `angle`
+/// is measured in the complex plane and `elevation` above it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Lighting {
+    pub enabled: bool,
+    pub height_field: HeightField,
+    pub angle: f32,
+    pub elevation: f32,
+    pub height: f32,
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+}
+
+impl Lighting {
+    /// Surface direction of the height field from the first and second derivatives of z with respect to c.
+    pub fn normal(&self, z: &Complex32, derivative: &Complex32, second_derivative: &Complex32) -> Complex32 {
+        match self.height_field {
+            HeightField::Smooth => z / derivative,
+            HeightField::Distance => {
+                let lo = 0.5 * z.norm_sqr().ln();
+                z * derivative * ((1.0 + lo) * (derivative * derivative).conj() - lo * (z * second_derivative).conj())
+            }
+        }
+    }
+
+    pub fn shade(&self, normal: &Complex32) -> f32 {
+        let length = normal.norm();
+        if !length.is_finite() || length == 0.0 {
+            return 1.0;
+        }
+        let normal = [normal.re / length, normal.im / length, 1.0 / self.height];
+        let normal = normalize(normal);
+
+        let (sin_angle, cos_angle) = self.angle.to_radians().sin_cos();
+        let (sin_elevation, cos_elevation) = self.elevation.to_radians().sin_cos();
+        let light = [cos_elevation * cos_angle, cos_elevation * sin_angle, sin_elevation];
+        let half = normalize([light[0], light[1], light[2] + 1.0]);
+
+        let lambert = dot(&normal, &light).max(0.0);
+        let specular = dot(&normal, &half).max(0.0).powf(self.shininess);
+        self.ambient + self.diffuse * lambert + self.specular * specular
+    }
+}
+
+fn dot(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(&v, &v).sqrt();
+    [v[0] / length, v[1] / length, v[2] / length]
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            height_field: HeightField::Smooth,
+            angle: 45.0,
+            elevation: 45.0,
+            height: 1.5,
+            ambient: 0.2,
+            diffuse: 0.8,
+            specular: 0.3,
+            shininess: 16.0,
+        }
+    }
+}
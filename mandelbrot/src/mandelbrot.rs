@@ -1,148 +1,554 @@
 use std::fmt::Debug;
+use std::path::Path;
 
-use colorgrad::{CatmullRomGradient, Color, Gradient, GradientBuilder};
+use colorgrad::Color;
 use num::complex::Complex32;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter, EnumString};
 
 use crate::boundary_scanner::BoundaryScanner;
 
-use super::range::Range;
-use super::rectangle::Rectangle;
+use super::antialiasing::Antialiasing;
+use super::hdr::ToneMapper;
+use super::lighting::Lighting;
+use super::orbit_trap::OrbitTrap;
+use super::palette::{self, Palette, PaletteError, PaletteFormat};
+use super::transform::{PixelMapping, ViewTransform};
 use super::vector::Vector;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Mandelbrot {
     pub width: usize,
     pub height: usize,
     pub position: Vector,
-    pub zoom: Vector,
+    /// Half size of the view in the complex plane, measured along the axis `aspect` picks.
+    pub scale: f32,
+    pub aspect: Aspect,
+    pub transform: ViewTransform,
     pub rendering: Rendering,
     pub bailout: f32,
     pub max_iterations: usize,
     pub chunk_size: usize,
     pub period_length: usize,
     pub coloring: Coloring,
+    pub trap: OrbitTrap,
+    pub stripe_density: f32,
+    pub average_blend: f32,
+    pub lighting: Lighting,
     pub exponent: f32,
-    pub(crate) palettes: Vec<(String, CatmullRomGradient)>,
+    /// Iterates z² + c with this fixed c and the pixel as starting point, giving the Julia set.
+    pub julia: Option<Vector>,
+    pub(crate) palettes: Vec<Palette>,
     pub selected_palette: usize,
+    pub palette_offset: f32,
+    pub palette_repeat: f32,
+    pub palette_reverse: bool,
+    pub cycle_speed: f32,
+    pub tone_mapping: ToneMapper,
+    pub antialiasing: Antialiasing,
 }
 
-#[derive(Debug, Clone, PartialEq, Display, EnumString, EnumIter)]
+#[derive(Debug, Clone, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
 pub enum Rendering {
     Smooth,
     Fast,
 }
 
-#[derive(Debug, Clone, PartialEq, Display, EnumString, EnumIter)]
+/// How the square of half size `scale` around the center becomes a view of
+/// the output's aspect ratio.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Display, EnumString, EnumIter, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum Aspect {
+    /// The square fits inside the view, the longer side shows more of the plane.
+    #[default]
+    Fit,
+    /// The square covers the view, the longer side is cropped to it.
+    Fill,
+    /// The square is stretched over the whole view, distorting it.
+    Stretch,
+}
+
+#[derive(Debug, Clone, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
 pub enum Coloring {
     Palette,
     LCH,
+    OrbitTrap,
+    Stripe,
+    TIA,
+    /// Colors every pixel by its atom domain, the iteration at which |z| last reached a new minimum.
+    AtomDomain,
+    /// Colors interior pixels by the period of their attracting cycle, up to `period_length + 1`.
+    Period,
+}
+
+impl Coloring {
+    pub fn uses_palette(&self) -> bool {
+        !matches!(self, Coloring::LCH)
+    }
+
+    pub(crate) fn uses_orbit(&self) -> bool {
+        !matches!(self, Coloring::Palette | Coloring::LCH)
+    }
+
+    /// Colorings that give interior pixels a color of their own instead of black.
+    pub fn colors_interior(&self) -> bool {
+        matches!(self, Coloring::AtomDomain | Coloring::Period)
+    }
 }
 
-pub fn rect_from_position(position: &Vector, zoom: &Vector) -> Rectangle {
-    Rectangle::new(
-        Vector::new(position.x - zoom.x, position.y - zoom.y),
-        Vector::new(position.x + zoom.x, position.y + zoom.y),
-    )
+/// Per-pixel result of iterating a point: the escape iteration, the
+/// normalized coloring value for the active `Coloring` and the lighting
+/// factor the color gets multiplied with.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub iterations: u32,
+    pub value: f32,
+    pub shade: f32,
+}
+
+impl Default for Sample {
+    fn default() -> Self {
+        Self {
+            iterations: 0,
+            value: 0.0,
+            shade: 1.0,
+        }
+    }
+}
+
+/// Samples of a whole frame, row by row, plus the supersamples of the
+/// pixels that were antialiased.
+#[derive(Debug, Clone, Default)]
+pub struct Field {
+    pub width: usize,
+    pub height: usize,
+    /// Row of the full view the field starts at.
+    pub first_row: usize,
+    pub samples: Vec<Sample>,
+    slots: Vec<u32>,
+    subsamples: Vec<Sample>,
+    samples_per_pixel: usize,
+}
+
+impl Field {
+    const NO_SLOT: u32 = u32::MAX;
+
+    /// Supersamples of the pixel at `index`, empty if it was sampled once.
+    pub fn subsamples(&self, index: usize) -> &[Sample] {
+        match self.slots.get(index) {
+            Some(&slot) if slot != Self::NO_SLOT => {
+                let start = slot as usize * self.samples_per_pixel;
+                &self.subsamples[start..start + self.samples_per_pixel]
+            }
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Orbit {
+    pub(crate) z: Complex32,
+    pub(crate) iterations: usize,
+    pub(crate) trap_distance: f32,
+    pub(crate) trap_iteration: usize,
+    pub(crate) average_sum: f32,
+    pub(crate) average_last: f32,
+    pub(crate) average_count: usize,
+    pub(crate) derivative: Complex32,
+    pub(crate) second_derivative: Complex32,
+    /// Iteration of the smallest |z| so far.
+    pub(crate) atom_domain: usize,
+    /// Length of the cycle the orbit was caught in, 0 if it escaped or none was found.
+    pub(crate) period: usize,
+}
+
+/// What an orbit keeps track of besides escaping and being caught in a cycle.
+#[derive(Debug, Clone, PartialEq)]
+struct Tracking {
+    /// Orbit coloring whose per-iteration state is kept, `None` keeps none.
+    coloring: Option<Coloring>,
+    /// First and second derivative, for lighting and distance estimates.
+    derivatives: bool,
 }
 
 impl Mandelbrot {
+    /// Changes the output size, the center and the scale stay the same.
     pub fn set_resolution(&mut self, width: usize, height: usize) {
         self.width = width;
         self.height = height;
     }
 
-    pub fn palettes(&self) -> &[(String, CatmullRomGradient)] {
+    /// Half width and half height of the view in the complex plane.
+    pub fn extent(&self) -> Vector {
+        self.extent_for(self.width as f32, self.height as f32)
+    }
+
+    /// Half width and half height of the view for a `width`×`height` output.
+    pub fn extent_for(&self, width: f32, height: f32) -> Vector {
+        let ratio = width / height;
+        let wide = match self.aspect {
+            Aspect::Fit => ratio >= 1.0,
+            Aspect::Fill => ratio < 1.0,
+            Aspect::Stretch => return Vector::new(self.scale, self.scale),
+        };
+        if wide {
+            Vector::new(self.scale * ratio, self.scale)
+        } else {
+            Vector::new(self.scale, self.scale / ratio)
+        }
+    }
+
+    /// Sets the scale so that the view spans at least `extent`, given as half
+    /// width and half height, at the current resolution.
+    pub fn fit_extent(&mut self, extent: &Vector) {
+        let current = self.extent();
+        self.scale *= (extent.x / current.x).max(extent.y / current.y);
+    }
+
+    pub fn palettes(&self) -> &[Palette] {
         &self.palettes
     }
 
-    pub fn ranges(&self) -> [Range; 4] {
-        let width_range = Range::new(0.0, self.width as f32);
-        let height_range = Range::new(0.0, self.height as f32);
+    pub fn palette_index(&self, name: &str) -> Option<usize> {
+        self.palettes.iter().position(|palette| palette.name == name)
+    }
 
-        let rect = rect_from_position(&self.position, &self.zoom);
-        let real_range = Range::new(rect.start.x, rect.end.x);
-        let imaginary_range = Range::new(rect.start.y, rect.end.y);
+    pub fn palette_mut(&mut self, index: usize) -> Option<&mut Palette> {
+        self.palettes.get_mut(index)
+    }
 
-        [width_range, height_range, real_range, imaginary_range]
+    /// Adds `palette`, replacing an existing palette of the same name in place. Returns its index.
+    pub fn insert_palette(&mut self, palette: Palette) -> usize {
+        if let Some(index) = self.palette_index(&palette.name) {
+            self.palettes[index] = palette;
+            index
+        } else {
+            self.palettes.push(palette);
+            self.palettes.len() - 1
+        }
+    }
+
+    /// Removes the palette at `index`. The last remaining palette cannot be removed.
+    pub fn remove_palette(&mut self, index: usize) -> Option<Palette> {
+        if self.palettes.len() <= 1 || index >= self.palettes.len() {
+            return None;
+        }
+        let palette = self.palettes.remove(index);
+        if self.selected_palette > index || self.selected_palette == self.palettes.len() {
+            self.selected_palette -= 1;
+        }
+        Some(palette)
+    }
+
+    /// Moves the palette at `from` to `to`, keeping the same palette selected.
+    pub fn move_palette(&mut self, from: usize, to: usize) {
+        if from >= self.palettes.len() || to >= self.palettes.len() {
+            return;
+        }
+        let palette = self.palettes.remove(from);
+        self.palettes.insert(to, palette);
+        self.selected_palette = match self.selected_palette {
+            selected if selected == from => to,
+            selected if from < selected && selected <= to => selected - 1,
+            selected if to <= selected && selected < from => selected + 1,
+            selected => selected,
+        };
+    }
+
+    /// Adds every gradient found in `source` and returns the index of the first one.
+    pub fn import_palette(&mut self, format: PaletteFormat, name: &str, source: &str) -> Result<usize, PaletteError> {
+        let palettes = palette::parse(format, name, source)?;
+        Ok(palettes
+            .into_iter()
+            .map(|palette| self.insert_palette(palette))
+            .min()
+            .unwrap_or_default())
+    }
+
+    pub fn load_palette(&mut self, path: impl AsRef<Path>) -> Result<usize, PaletteError> {
+        let palettes = palette::load(path)?;
+        Ok(palettes
+            .into_iter()
+            .map(|palette| self.insert_palette(palette))
+            .min()
+            .unwrap_or_default())
+    }
+
+    /// Maps pixels of the current resolution to the complex plane.
+    pub fn mapping(&self) -> PixelMapping {
+        self.pixel_mapping(self.width as f32, self.height as f32)
+    }
+
+    /// Maps pixels of a `width`×`height` image of the current view to the complex plane.
+    pub fn pixel_mapping(&self, width: f32, height: f32) -> PixelMapping {
+        let transform = |x: f32, y: f32| {
+            let (re, im) = self.transform.apply(x, y);
+            Complex32::new(re, im)
+        };
+        let extent = self.extent_for(width, height);
+        PixelMapping {
+            origin: Complex32::new(self.position.x, self.position.y) + transform(-extent.x, -extent.y),
+            step_x: transform(2.0 * extent.x / width, 0.0),
+            step_y: transform(0.0, 2.0 * extent.y / height),
+        }
     }
 
     pub fn render(&self, pixels: &mut [u8]) {
+        let field = self.compute_field();
+        self.colorize(&field, pixels);
+    }
+
+    /// Renders only `rows` of the current view, with the same pixels as a full render.
+    pub fn render_rows(&self, rows: std::ops::Range<usize>, pixels: &mut [u8]) {
+        let field = self.compute_rows(rows);
+        self.colorize(&field, pixels);
+    }
+
+    /// Iterates every pixel of the current view without coloring it.
+    pub fn compute_field(&self) -> Field {
+        self.compute_rows(0..self.height)
+    }
+
+    /// Iterates the pixels of `rows` of the current view.
+    pub fn compute_rows(&self, rows: std::ops::Range<usize>) -> Field {
+        let rows = rows.start.min(self.height)..rows.end.min(self.height);
+        let mut samples = vec![Sample::default(); self.width * rows.len()];
+        // Boundary tracing fills areas of equal escape time with one sample, so
+        // colorings and lighting that vary within them are iterated per pixel.
         match self.rendering {
-            Rendering::Smooth => self.render_smooth(pixels),
-            Rendering::Fast => self.render_fast(pixels),
+            Rendering::Fast if !self.coloring.uses_orbit() && !self.lighting.enabled => {
+                self.field_fast(&mut samples, rows.clone())
+            }
+            _ => self.field_smooth(&mut samples, rows.start),
+        }
+        let mut field = Field {
+            width: self.width,
+            height: rows.len(),
+            first_row: rows.start,
+            samples,
+            ..Default::default()
+        };
+        if self.antialiasing.enabled() {
+            self.supersample(&mut field);
+        }
+        field
+    }
+
+    fn supersample(&self, field: &mut Field) {
+        let mapping = self.mapping();
+        let (width, height) = (field.width, field.height);
+        let offset = field.first_row * width;
+
+        let pixels: Vec<usize> = (0..field.samples.len())
+            .into_par_iter()
+            .filter(|&index| {
+                if !self.antialiasing.adaptive {
+                    return true;
+                }
+                let (x, y) = (index % width, index / width);
+                let sample = &field.samples[index];
+                let neighbours = [
+                    (x > 0).then(|| index - 1),
+                    (x + 1 < width).then(|| index + 1),
+                    (y > 0).then(|| index - width),
+                    (y + 1 < height).then(|| index + width),
+                ];
+                // Rows next to a partial field are sampled directly, so the
+                // decision matches a render of the whole view.
+                let outside = [
+                    (y == 0 && field.first_row > 0).then(|| field.first_row - 1),
+                    (y + 1 == height && field.first_row + height < self.height).then_some(field.first_row + height),
+                ];
+                neighbours
+                    .into_iter()
+                    .flatten()
+                    .any(|neighbour| self.differs(sample, &field.samples[neighbour]))
+                    || outside
+                        .into_iter()
+                        .flatten()
+                        .any(|row| self.differs(sample, &self.sample(&mapping.map(x as f32, row as f32))))
+            })
+            .collect();
+
+        field.subsamples = pixels
+            .par_iter()
+            .flat_map_iter(|&index| {
+                let index = offset + index;
+                let x = (index % width) as f32;
+                let y = (index / width) as f32;
+                self.antialiasing
+                    .offsets(index)
+                    .into_iter()
+                    .map(move |(dx, dy)| self.sample(&mapping.map(x + dx, y + dy)))
+            })
+            .collect();
+        field.samples_per_pixel = self.antialiasing.samples * self.antialiasing.samples;
+        field.slots = vec![Field::NO_SLOT; field.samples.len()];
+        for (slot, &index) in pixels.iter().enumerate() {
+            field.slots[index] = slot as u32;
+        }
+    }
+
+    fn differs(&self, a: &Sample, b: &Sample) -> bool {
+        let threshold = self.antialiasing.threshold;
+        let interior = |sample: &Sample| sample.iterations as usize >= self.max_iterations;
+        interior(a) != interior(b) || (a.value - b.value).abs() > threshold || (a.shade - b.shade).abs() > threshold
+    }
+
+    /// Colors a previously computed field. Only the coloring parameters are
+    /// read, so palette changes do not require iterating again.
+    pub fn colorize(&self, field: &Field, pixels: &mut [u8]) {
+        let lut = self.build_lut();
+        // Without dithering the encoding of a color does not depend on the pixel,
+        // so pixels that are neither shaded nor supersampled read it from a table.
+        let encoded: Option<Vec<[u8; 4]>> = (!self.tone_mapping.dither).then(|| {
+            lut.iter()
+                .map(|color| self.tone_mapping.encode_rgba8(color, 0))
+                .collect()
+        });
+        let black = self.tone_mapping.encode_rgba8(&[0.0, 0.0, 0.0, 1.0], 0);
+        pixels
+            .par_chunks_exact_mut(4)
+            .zip(field.samples.par_iter())
+            .enumerate()
+            .for_each(|(index, (pixel, sample))| {
+                if let Some(encoded) = &encoded
+                    && sample.shade == 1.0
+                    && field.subsamples(index).is_empty()
+                {
+                    pixel.copy_from_slice(&self.lut_index(sample).map_or(black, |slot| encoded[slot]));
+                    return;
+                }
+                let color = self.pixel_color(&lut, field, index, sample);
+                let index = field.first_row * field.width + index;
+                pixel.copy_from_slice(&self.tone_mapping.encode_rgba8(&color, index));
+            });
+    }
+
+    /// Renders linear-light RGBA without tone mapping, four floats per pixel.
+    pub fn render_hdr(&self, pixels: &mut [f32]) {
+        let field = self.compute_field();
+        self.colorize_hdr(&field, pixels);
+    }
+
+    pub fn colorize_hdr(&self, field: &Field, pixels: &mut [f32]) {
+        let lut = self.build_lut();
+        pixels
+            .par_chunks_exact_mut(4)
+            .zip(field.samples.par_iter())
+            .enumerate()
+            .for_each(|(index, (pixel, sample))| {
+                pixel.copy_from_slice(&self.pixel_color(&lut, field, index, sample));
+            });
+    }
+
+    fn build_lut(&self) -> Vec<[f32; 4]> {
+        if self.uses_fast_lut() {
+            self.build_fast_lut()
+        } else {
+            self.build_smooth_lut()
         }
     }
 
+    fn uses_fast_lut(&self) -> bool {
+        self.rendering == Rendering::Fast && !self.coloring.uses_orbit()
+    }
+
+    /// Linear color of a pixel, averaging its supersamples if it has any.
+    #[inline]
+    fn pixel_color(&self, lut: &[[f32; 4]], field: &Field, index: usize, sample: &Sample) -> [f32; 4] {
+        let subsamples = field.subsamples(index);
+        if subsamples.is_empty() {
+            return self.linear_color(lut, sample);
+        }
+        let mut sum = [0.0; 4];
+        for subsample in subsamples {
+            let color = self.linear_color(lut, subsample);
+            for (sum, channel) in sum.iter_mut().zip(color) {
+                *sum += channel;
+            }
+        }
+        sum.map(|channel| channel / subsamples.len() as f32)
+    }
+
+    /// Entry of the color table for `sample`, `None` for black interior pixels.
+    #[inline]
+    fn lut_index(&self, sample: &Sample) -> Option<usize> {
+        let iterations = sample.iterations as usize;
+        if iterations >= self.max_iterations && !self.coloring.colors_interior() {
+            return None;
+        }
+        if self.uses_fast_lut() {
+            Some(iterations)
+        } else {
+            let idx = (sample.value * (Self::SMOOTH_LUT_SIZE - 1) as f32) as usize;
+            Some(idx.min(Self::SMOOTH_LUT_SIZE - 1))
+        }
+    }
+
+    #[inline]
+    fn linear_color(&self, lut: &[[f32; 4]], sample: &Sample) -> [f32; 4] {
+        let Some(index) = self.lut_index(sample) else {
+            return [0.0, 0.0, 0.0, 1.0];
+        };
+        let [r, g, b, a] = lut[index];
+        [r * sample.shade, g * sample.shade, b * sample.shade, a]
+    }
+
     const SMOOTH_LUT_SIZE: usize = 4096;
 
-    fn build_smooth_lut(&self) -> Vec<[u8; 4]> {
+    fn build_smooth_lut(&self) -> Vec<[f32; 4]> {
         (0..Self::SMOOTH_LUT_SIZE)
             .map(|i| {
                 let s = i as f32 / Self::SMOOTH_LUT_SIZE as f32;
-                self.color_at(s).to_rgba8()
+                self.color_at(s).to_linear_rgba()
             })
             .collect()
     }
 
-    fn build_fast_lut(&self) -> Vec<[u8; 4]> {
+    fn build_fast_lut(&self) -> Vec<[f32; 4]> {
         (0..self.max_iterations)
-            .map(|i| self.color(None, i).to_rgba8())
+            .map(|i| self.color(None, i).to_linear_rgba())
             .collect()
     }
 
-    fn render_smooth(&self, pixels: &mut [u8]) {
-        let [width_range, height_range, real_range, imaginary_range] = self.ranges();
-        let lut = self.build_smooth_lut();
-        let max_index = (Self::SMOOTH_LUT_SIZE - 1) as f32;
+    fn field_smooth(&self, samples: &mut [Sample], first_row: usize) {
+        let mapping = self.mapping();
 
-        pixels
-            .par_chunks_exact_mut(4)
+        samples
+            .par_iter_mut()
             .enumerate()
             .by_uniform_blocks(self.chunk_size)
-            .for_each(|(index, pixel)| {
+            .for_each(|(index, sample)| {
                 let x = (index % self.width) as f32;
-                let y = (index / self.width) as f32;
+                let y = (first_row + index / self.width) as f32;
 
-                let c = Complex32::new(
-                    Range::scale(&width_range, x, &real_range),
-                    Range::scale(&height_range, y, &imaginary_range),
-                );
-
-                let (z, iterations) = self.iterate(&c);
-                if iterations < self.max_iterations {
-                    let s = self.exponential(self.smooth(&z, iterations));
-                    let idx = (s * max_index) as usize;
-                    pixel.copy_from_slice(&lut[idx.min(Self::SMOOTH_LUT_SIZE - 1)]);
-                } else {
-                    pixel.copy_from_slice(&[0, 0, 0, 0xFF]);
-                }
+                *sample = self.sample(&mapping.map(x, y));
             })
     }
 
-    fn render_fast(&self, pixels: &mut [u8]) {
-        let lut = self.build_fast_lut();
-        let rows = self.height / rayon::current_num_threads();
-        let chunk_size = self.width * rows * 4;
-        pixels
-            .par_chunks_exact_mut(chunk_size)
+    fn field_fast(&self, samples: &mut [Sample], rows: std::ops::Range<usize>) {
+        let chunk_rows = rows.len().div_ceil(rayon::current_num_threads()).max(1);
+        samples
+            .par_chunks_mut(self.width * chunk_rows)
             .enumerate()
-            .for_each(|(index, pixels)| {
-                let start = index * rows;
-                let mut boundary_scanner = BoundaryScanner::new(self, start, start + rows);
-                let data = boundary_scanner.run();
-                pixels.chunks_exact_mut(4).enumerate().for_each(|(index, pixel)| {
-                    let iterations = data[index] as usize;
-                    if iterations < self.max_iterations {
-                        pixel.copy_from_slice(&lut[iterations]);
-                    } else {
-                        pixel.copy_from_slice(&[0, 0, 0, 0xFF]);
-                    }
-                });
+            .for_each(|(index, samples)| {
+                let start = rows.start + index * chunk_rows;
+                let end = start + samples.len() / self.width;
+                let mut boundary_scanner = BoundaryScanner::new(self, start, end);
+                samples.copy_from_slice(boundary_scanner.run());
             });
     }
 
+    /// Advances the palette offset by `seconds` at `cycle_speed` palette lengths per second.
+    pub fn cycle_palette(&mut self, seconds: f32) {
+        self.palette_offset = (self.palette_offset + self.cycle_speed * seconds).rem_euclid(1.0);
+    }
+
     #[inline]
     fn is_interior(c: &Complex32) -> bool {
         let im2 = c.im * c.im;
@@ -153,18 +559,211 @@ impl Mandelbrot {
         q * (q + (c.re - 0.25)) < 0.25 * im2 || p2 * p2 + im2 < 0.0625
     }
 
+    /// Starting point and constant of the iteration for the point `c` of the plane.
+    #[inline]
+    fn start(&self, c: &Complex32) -> (Complex32, Complex32) {
+        match &self.julia {
+            Some(julia) => (*c, Complex32::new(julia.x, julia.y)),
+            None => (Complex32::ZERO, *c),
+        }
+    }
+
     pub(crate) fn iterate(&self, c: &Complex32) -> (Complex32, usize) {
-        if Self::is_interior(c) {
+        if self.julia.is_none() && Self::is_interior(c) {
             (Complex32::ZERO, self.max_iterations)
         } else {
-            unsafe { self.iterate_inner(c) }
+            let (z, c) = self.start(c);
+            // Only the SIMD variants of `iterate_inner` are unsafe.
+            #[allow(unused_unsafe)]
+            unsafe {
+                self.iterate_inner(&z, &c)
+            }
+        }
+    }
+
+    pub fn sample(&self, c: &Complex32) -> Sample {
+        if self.coloring.uses_orbit() || self.lighting.enabled {
+            let orbit = self.iterate_orbit(c);
+            let interior = orbit.iterations >= self.max_iterations;
+            let shade = if self.lighting.enabled && !interior {
+                let normal = self
+                    .lighting
+                    .normal(&orbit.z, &orbit.derivative, &orbit.second_derivative);
+                self.lighting.shade(&normal)
+            } else if interior && self.coloring == Coloring::Period && orbit.period == 0 {
+                // Interior without a detected cycle stays black.
+                0.0
+            } else {
+                1.0
+            };
+            Sample {
+                iterations: orbit.iterations as u32,
+                value: self.orbit_value(&orbit),
+                shade,
+            }
+        } else {
+            let (z, iterations) = self.iterate(c);
+            Sample {
+                iterations: iterations as u32,
+                value: self.exponential(self.smooth(&z, iterations)),
+                shade: 1.0,
+            }
+        }
+    }
+
+    fn orbit_value(&self, orbit: &Orbit) -> f32 {
+        match self.coloring {
+            Coloring::OrbitTrap => self
+                .trap
+                .value(orbit.trap_distance, orbit.trap_iteration, self.max_iterations),
+            Coloring::Stripe | Coloring::TIA if orbit.average_count > 1 => {
+                // Interpolate between the averages with and without the last term
+                // using the fractional part of the smooth iteration count.
+                let count = orbit.average_count as f32;
+                let average = orbit.average_sum / count;
+                let last = orbit.average_last / (count - 1.0);
+                let smooth = self.smooth(&orbit.z, orbit.iterations);
+                let fraction = smooth - smooth.floor();
+                let average = last + (average - last) * fraction;
+                let gradient = self.exponential(smooth);
+                (gradient + (average - gradient) * self.average_blend).clamp(0.0, 1.0)
+            }
+            Coloring::AtomDomain => Self::period_value(orbit.atom_domain),
+            Coloring::Period if orbit.iterations >= self.max_iterations => Self::period_value(orbit.period),
+            _ => self.exponential(self.smooth(&orbit.z, orbit.iterations)),
+        }
+    }
+
+    /// Spreads periods over the palette so that nearby periods get distant colors.
+    fn period_value(period: usize) -> f32 {
+        (period as f32 * 0.618_034).fract()
+    }
+
+    /// Scalar iteration that keeps the per-orbit state needed by orbit based colorings.
+    pub(crate) fn iterate_orbit(&self, point: &Complex32) -> Orbit {
+        self.iterate_tracking(
+            point,
+            Tracking {
+                coloring: Some(self.coloring.clone()),
+                derivatives: self.lighting.enabled,
+            },
+        )
+    }
+
+    /// Iterates `point` for its escape time and cycle only, whatever the coloring and lighting.
+    pub(crate) fn escape(&self, point: &Complex32) -> Orbit {
+        self.iterate_tracking(
+            point,
+            Tracking {
+                coloring: None,
+                derivatives: false,
+            },
+        )
+    }
+
+    /// Distance from `point` to the set estimated from the derivative of its
+    /// orbit, `None` if it did not escape.
+    pub(crate) fn distance_estimate(&self, point: &Complex32) -> Option<f32> {
+        let orbit = self.iterate_tracking(
+            point,
+            Tracking {
+                coloring: None,
+                derivatives: true,
+            },
+        );
+        let z = orbit.z.norm();
+        let distance = 0.5 * z * z.ln() / orbit.derivative.norm();
+        (orbit.iterations < self.max_iterations && distance.is_finite()).then_some(distance)
+    }
+
+    fn iterate_tracking(&self, point: &Complex32, tracking: Tracking) -> Orbit {
+        let (z, c) = self.start(point);
+        let c = &c;
+        // Derivatives are taken with respect to c for the Mandelbrot set and z for Julia sets.
+        let (derivative, increment) = match self.julia {
+            Some(_) => (Complex32::ONE, Complex32::ZERO),
+            None => (Complex32::ZERO, Complex32::ONE),
+        };
+        let mut orbit = Orbit {
+            z,
+            iterations: 0,
+            trap_distance: f32::INFINITY,
+            trap_iteration: 0,
+            average_sum: 0.0,
+            average_last: 0.0,
+            average_count: 0,
+            derivative,
+            second_derivative: Complex32::ZERO,
+            atom_domain: 0,
+            period: 0,
+        };
+        // Atom domains extend into the cardioid and the period 2 bulb, so those are iterated too.
+        if self.julia.is_none() && tracking.coloring != Some(Coloring::AtomDomain) && Self::is_interior(c) {
+            orbit.iterations = self.max_iterations;
+            let bulb = (c.re + 1.0) * (c.re + 1.0) + c.im * c.im < 0.0625;
+            orbit.period = if bulb { 2 } else { 1 };
+            return orbit;
+        }
+        let mut old = Complex32::ZERO;
+        let mut period = 0;
+        let mut atom_norm = f32::INFINITY;
+        let c_norm = c.norm();
+        while orbit.z.norm_sqr() < self.bailout && orbit.iterations < self.max_iterations {
+            let previous = orbit.z;
+            if tracking.derivatives {
+                orbit.second_derivative =
+                    2.0 * (orbit.derivative * orbit.derivative + previous * orbit.second_derivative);
+                orbit.derivative = 2.0 * previous * orbit.derivative + increment;
+            }
+            orbit.z = orbit.z * orbit.z + c;
+            if orbit.z == old {
+                orbit.iterations = self.max_iterations;
+                orbit.period = period + 1;
+                return orbit;
+            }
+            orbit.iterations += 1;
+            match tracking.coloring {
+                Some(Coloring::OrbitTrap) => {
+                    let distance = self.trap.distance(&orbit.z);
+                    if distance < orbit.trap_distance {
+                        orbit.trap_distance = distance;
+                        orbit.trap_iteration = orbit.iterations;
+                    }
+                }
+                Some(Coloring::Stripe) => {
+                    orbit.average_last = orbit.average_sum;
+                    orbit.average_sum += 0.5 * f32::sin(self.stripe_density * orbit.z.arg()) + 0.5;
+                    orbit.average_count += 1;
+                }
+                Some(Coloring::TIA) if orbit.iterations > 1 => {
+                    let previous = previous.norm_sqr();
+                    let low = f32::abs(previous - c_norm);
+                    let high = previous + c_norm;
+                    if high > low {
+                        orbit.average_last = orbit.average_sum;
+                        orbit.average_sum += (orbit.z.norm() - low) / (high - low);
+                        orbit.average_count += 1;
+                    }
+                }
+                Some(Coloring::AtomDomain) if orbit.z.norm_sqr() < atom_norm => {
+                    atom_norm = orbit.z.norm_sqr();
+                    orbit.atom_domain = orbit.iterations;
+                }
+                _ => (),
+            }
+            period += 1;
+            if period > self.period_length {
+                period = 0;
+                old = orbit.z;
+            }
         }
+        orbit
     }
 
     #[cfg(all(not(target_arch = "aarch64"), not(target_family = "wasm")))]
-    pub(crate) fn iterate_inner(&self, c: &Complex32) -> (Complex32, usize) {
+    pub(crate) fn iterate_inner(&self, z: &Complex32, c: &Complex32) -> (Complex32, usize) {
         use num::traits::MulAddAssign;
-        let mut z: Complex32 = Complex32::ZERO;
+        let mut z: Complex32 = *z;
         let mut iterations = 0;
         let mut old: Complex32 = Complex32::ZERO;
         let mut period = 0;
@@ -184,12 +783,12 @@ impl Mandelbrot {
     }
 
     #[cfg(all(target_family = "wasm", target_feature = "simd128"))]
-    pub(crate) unsafe fn iterate_inner(&self, c: &Complex32) -> (Complex32, usize) {
+    pub(crate) unsafe fn iterate_inner(&self, z: &Complex32, c: &Complex32) -> (Complex32, usize) {
         use core::arch::wasm32::*;
         {
             // Pack complex into lower 2 lanes of v128: [re, im, 0, 0]
             let c = f32x4(c.re, c.im, 0.0, 0.0);
-            let mut z = f32x4_splat(0.0);
+            let mut z = f32x4(z.re, z.im, 0.0, 0.0);
             let mut iterations = 0;
             let mut old = f32x4_splat(0.0);
             let mut period = 0;
@@ -229,12 +828,12 @@ impl Mandelbrot {
     }
 
     #[cfg(all(target_arch = "aarch64", target_feature = "fcma"))]
-    pub(crate) unsafe fn iterate_inner(&self, c: &Complex32) -> (Complex32, usize) {
+    pub(crate) unsafe fn iterate_inner(&self, z: &Complex32, c: &Complex32) -> (Complex32, usize) {
         use core::arch::aarch64::*;
         use core::mem::transmute;
         unsafe {
             let c: float32x2_t = transmute(*c);
-            let mut z = vmov_n_f32(0.0);
+            let mut z: float32x2_t = transmute(*z);
             let mut iterations = 0;
             let mut old = vmov_n_f32(0.0);
             let mut period = 0;
@@ -260,19 +859,12 @@ impl Mandelbrot {
     }
 
     pub fn zoom(&mut self, x: f32, y: f32, zoom_factor: f32) {
-        let width_range = Range::new(0.0, self.width as f32);
-        let height_range = Range::new(0.0, self.height as f32);
-        let selection = rect_from_position(&self.position, &self.zoom);
-        let real_range = Range::new(selection.start.x, selection.end.x);
-        let imaginary_range = Range::new(selection.start.y, selection.end.y);
+        let target = self.mapping().map(x, y);
         self.position = Vector {
-            x: Range::scale(&width_range, x, &real_range),
-            y: Range::scale(&height_range, y, &imaginary_range),
-        };
-        self.zoom = Vector {
-            x: self.zoom.x * zoom_factor,
-            y: self.zoom.y * zoom_factor,
+            x: target.re,
+            y: target.im,
         };
+        self.scale *= zoom_factor;
     }
 
     fn smooth(&self, z: &Complex32, iterations: usize) -> f32 {
@@ -289,11 +881,16 @@ impl Mandelbrot {
     }
 
     pub fn color_at(&self, s: f32) -> Color {
+        let s = s * self.palette_repeat + self.palette_offset;
+        let s = if (0.0..=1.0).contains(&s) { s } else { s.rem_euclid(1.0) };
+        let s = if self.palette_reverse { 1.0 - s } else { s };
         match self.coloring {
-            Coloring::Palette => {
-                let (_, palette) = &self.palettes[self.selected_palette];
-                palette.at(f32::powf(s, 1.0 / 3.0))
-            }
+            Coloring::Palette
+            | Coloring::OrbitTrap
+            | Coloring::Stripe
+            | Coloring::TIA
+            | Coloring::AtomDomain
+            | Coloring::Period => self.palettes[self.selected_palette].at(f32::powf(s, 1.0 / 3.0)),
             Coloring::LCH => {
                 let s = f32::powf(s, 1.5);
                 let v = 1.0 - f32::powf(f32::cos(std::f32::consts::PI * s), 2.0);
@@ -324,34 +921,38 @@ impl Default for Mandelbrot {
             ("Cold Teal", vec!["#1B3A4B", "#3D6B7E", "#71C9CE", "#A6E3E9", "#E3FDFD"]),
             ("Sunset", vec!["#F9ED69", "#F08A5D", "#B83B5E", "#6A2C70"]),
         ];
-        let palettes: Vec<(String, CatmullRomGradient)> = palettes
+        let palettes: Vec<Palette> = palettes
             .into_iter()
-            .map(|(name, hex_list)| {
-                (
-                    name.to_string(),
-                    GradientBuilder::new()
-                        .html_colors(&hex_list)
-                        .mode(colorgrad::BlendMode::Oklab)
-                        .build::<CatmullRomGradient>()
-                        .unwrap(),
-                )
-            })
+            .map(|(name, hex_list)| Palette::from_html(name, &hex_list).unwrap())
             .collect();
 
         Self {
             width: 1280,
             height: 720,
             position: Vector { x: -0.5, y: 0.0 },
-            zoom: Vector { x: 2.0, y: 1.125 },
+            scale: 1.125,
+            aspect: Aspect::default(),
+            transform: ViewTransform::default(),
             rendering: Rendering::Fast,
             bailout: f32::powf(2.0, 16.0),
             max_iterations: 1000,
             chunk_size: usize::pow(2, 8),
             period_length: 20,
             coloring: Coloring::LCH,
+            trap: OrbitTrap::default(),
+            stripe_density: 5.0,
+            average_blend: 1.0,
+            lighting: Lighting::default(),
             exponent: 1.0,
+            julia: None,
             palettes,
             selected_palette: 0,
+            palette_offset: 0.0,
+            palette_repeat: 1.0,
+            palette_reverse: false,
+            cycle_speed: 0.1,
+            tone_mapping: ToneMapper::default(),
+            antialiasing: Antialiasing::default(),
         }
     }
 }
@@ -0,0 +1,176 @@
+use std::fmt;
+
+use num::complex::Complex64;
+
+use super::mandelbrot::Mandelbrot;
+use super::vector::Vector;
+
+/// Newton steps before a refinement is given up.
+const NEWTON_STEPS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MisiurewiczError {
+    /// Julia sets have no Misiurewicz points to search for.
+    Julia,
+    /// Preperiod and period must be at least 1.
+    Order,
+    /// Newton iteration left the plane or settled on a point of another order.
+    NotConverged,
+    /// The orbit does not separate from the cycle, so no view scale follows from it.
+    Degenerate,
+}
+
+impl fmt::Display for MisiurewiczError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Julia => f.write_str("Misiurewicz points can only be searched in the Mandelbrot set"),
+            Self::Order => f.write_str("preperiod and period must be at least 1"),
+            Self::NotConverged => f.write_str("Newton iteration did not converge"),
+            Self::Degenerate => f.write_str("the point has no finite structure scale"),
+        }
+    }
+}
+
+impl std::error::Error for MisiurewiczError {}
+
+/// Parameter whose critical orbit lands on a repelling cycle: z_{preperiod + period} = z_preperiod
+/// with z_0 = 0. The set around it looks like the Julia set around the cycle, spirals where
+/// the multiplier turns and "embedded Julia sets" deeper down.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Misiurewicz {
+    pub position: Complex64,
+    pub preperiod: usize,
+    pub period: usize,
+    /// Derivative of the cycle, each period zooms in by its magnitude and turns by its argument.
+    pub multiplier: Complex64,
+    /// Half size of a view that shows the structure around the point.
+    pub scale: f64,
+}
+
+impl Misiurewicz {
+    /// Refines `seed` to the Misiurewicz point of the given order next to it.
+    pub fn find(seed: Complex64, preperiod: usize, period: usize) -> Result<Self, MisiurewiczError> {
+        if preperiod == 0 || period == 0 {
+            return Err(MisiurewiczError::Order);
+        }
+        let position = refine(seed, preperiod, period).ok_or(MisiurewiczError::NotConverged)?;
+        let (preperiod, period) = exact_order(position, preperiod, period).ok_or(MisiurewiczError::NotConverged)?;
+        let orbit = orbit(position, preperiod + period);
+        let (z, dz) = orbit[preperiod];
+        // Multiplier and parameter derivative of the cycle through z.
+        let (mut u, mut du, mut multiplier) = (z, Complex64::new(0.0, 0.0), Complex64::new(1.0, 0.0));
+        for _ in 0..period {
+            multiplier *= 2.0 * u;
+            du = 2.0 * u * du + 1.0;
+            u = u * u + position;
+        }
+        let cycle_derivative = du / (1.0 - multiplier);
+        // Offsets from the point move the orbit away from the cycle by `separation` times
+        // their size. The set shows the Julia set around z as far as the cycle can be
+        // linearized, which is limited by the critical point at 0 and shrinks as the
+        // multiplier approaches the unit circle.
+        let separation = (dz - cycle_derivative).norm();
+        let linear_radius = z.norm() * (1.0 - 1.0 / multiplier.norm());
+        let scale = linear_radius / separation;
+        // The view is rendered in f32, which must still tell the scale from zero.
+        if !(scale.is_finite() && scale as f32 > 0.0) {
+            return Err(MisiurewiczError::Degenerate);
+        }
+        Ok(Self {
+            position,
+            preperiod,
+            period,
+            multiplier,
+            scale,
+        })
+    }
+
+    /// Tries every order up to `max_preperiod` and `max_period` from `c` and returns the
+    /// point closest to it.
+    pub fn find_near(
+        mandelbrot: &Mandelbrot,
+        c: Complex64,
+        max_preperiod: usize,
+        max_period: usize,
+    ) -> Result<Self, MisiurewiczError> {
+        if mandelbrot.julia.is_some() {
+            return Err(MisiurewiczError::Julia);
+        }
+        (1..=max_preperiod)
+            .flat_map(|preperiod| (1..=max_period).map(move |period| (preperiod, period)))
+            .filter_map(|(preperiod, period)| Self::find(c, preperiod, period).ok())
+            .min_by(|a, b| (a.position - c).norm().total_cmp(&(b.position - c).norm()))
+            .ok_or(MisiurewiczError::NotConverged)
+    }
+
+    /// Parameters centered on the point at its structure scale, everything except the view
+    /// is taken from `base`.
+    pub fn view(&self, base: &Mandelbrot) -> Mandelbrot {
+        Mandelbrot {
+            position: Vector::new(self.position.re as f32, self.position.im as f32),
+            scale: self.scale as f32,
+            ..base.clone()
+        }
+    }
+}
+
+/// z_i and dz_i/dc for i in 0..=length, starting at z_0 = 0.
+fn orbit(c: Complex64, length: usize) -> Vec<(Complex64, Complex64)> {
+    let mut orbit = Vec::with_capacity(length + 1);
+    let (mut z, mut dz) = (Complex64::new(0.0, 0.0), Complex64::new(0.0, 0.0));
+    orbit.push((z, dz));
+    for _ in 0..length {
+        dz = 2.0 * z * dz + 1.0;
+        z = z * z + c;
+        orbit.push((z, dz));
+    }
+    orbit
+}
+
+/// Newton iteration on z_{preperiod + period}(c) - z_preperiod(c), with the roots of lower
+/// preperiods and of periods dividing `period` divided out.
+pub fn refine(seed: Complex64, preperiod: usize, period: usize) -> Option<Complex64> {
+    let mut c = seed;
+    for _ in 0..NEWTON_STEPS {
+        let orbit = orbit(c, preperiod + period);
+        let difference = |a: usize, b: usize| (orbit[a].0 - orbit[b].0, orbit[a].1 - orbit[b].1);
+        // Logarithmic derivative of the deflated function.
+        let (f, df) = difference(preperiod + period, preperiod);
+        if f.norm() == 0.0 {
+            break;
+        }
+        let mut ratio = df / f;
+        for i in 0..preperiod {
+            let (h, dh) = difference(i + period, i);
+            ratio -= dh / h;
+        }
+        for divisor in (1..period).filter(|divisor| period.is_multiple_of(*divisor)) {
+            let (h, dh) = difference(preperiod + divisor, preperiod);
+            ratio -= dh / h;
+        }
+        let step = 1.0 / ratio;
+        if !step.is_finite() {
+            return None;
+        }
+        c -= step;
+        if step.norm() <= c.norm().max(1.0) * f64::EPSILON * 4.0 {
+            break;
+        }
+    }
+    Some(c).filter(|c| c.is_finite() && exact_order(*c, preperiod, period).is_some())
+}
+
+/// Smallest preperiod and period for which `c` is within rounding distance of a
+/// Misiurewicz point, `None` if it is none or periodic.
+fn exact_order(c: Complex64, preperiod: usize, period: usize) -> Option<(usize, usize)> {
+    let tolerance = c.norm().max(1.0) * 1e-10;
+    let orbit = orbit(c, preperiod + period);
+    let close = |a: usize, b: usize| {
+        let (h, dh) = (orbit[a].0 - orbit[b].0, orbit[a].1 - orbit[b].1);
+        (h / dh).norm() < tolerance || h.norm() < f64::EPSILON
+    };
+    let period =
+        (1..=period).find(|divisor| period.is_multiple_of(*divisor) && close(preperiod + divisor, preperiod))?;
+    let preperiod = (0..=preperiod).find(|i| close(i + period, *i))?;
+    (preperiod > 0).then_some((preperiod, period))
+}
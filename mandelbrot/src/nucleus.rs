@@ -0,0 +1,174 @@
+use std::fmt;
+
+use num::complex::Complex64;
+
+use super::mandelbrot::Mandelbrot;
+use super::transform::ViewTransform;
+use super::vector::Vector;
+
+/// Newton steps before a refinement is given up.
+const NEWTON_STEPS: usize = 64;
+
+/// Radii of the search disk a nucleus may lie from its center.
+const DISTANCE_TOLERANCE: f64 = 4.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NucleusError {
+    /// Julia sets have no minibrots to search for.
+    Julia,
+    /// No component up to the maximum period lies within the search radius.
+    NoPeriod,
+    /// Newton iteration left the plane or did not settle.
+    NotConverged,
+    /// Newton iteration settled on a nucleus far outside the search radius.
+    Distant,
+}
+
+impl fmt::Display for NucleusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Julia => f.write_str("nuclei can only be searched in the Mandelbrot set"),
+            Self::NoPeriod => f.write_str("no periodic component within the search radius"),
+            Self::NotConverged => f.write_str("Newton iteration did not converge"),
+            Self::Distant => f.write_str("Newton iteration settled far outside the search radius"),
+        }
+    }
+}
+
+impl std::error::Error for NucleusError {}
+
+/// Center of a hyperbolic component, the point whose orbit returns to 0
+/// after `period` iterations. All values are in f64, far below the pixel
+/// size of the f32 renderer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nucleus {
+    pub position: Complex64,
+    pub period: usize,
+    /// Size of the minibrot relative to the whole set.
+    pub size: f64,
+    /// Counterclockwise rotation of the minibrot relative to the whole set, in degrees.
+    pub orientation: f64,
+}
+
+impl Nucleus {
+    /// Finds the nucleus of the lowest period component within `radius` of
+    /// `center`, with a period of at most `max_period`.
+    pub fn find(center: Complex64, radius: f64, max_period: usize) -> Result<Self, NucleusError> {
+        let period = find_period(center, radius, max_period).ok_or(NucleusError::NoPeriod)?;
+        let position = refine(center, period).ok_or(NucleusError::NotConverged)?;
+        // The period estimate is loose, Newton may leave for an unrelated component.
+        if (position - center).norm() > DISTANCE_TOLERANCE * radius {
+            return Err(NucleusError::Distant);
+        }
+        // Newton may settle on the nucleus of a component whose period divides the estimate.
+        let period = exact_period(position, period).ok_or(NucleusError::NotConverged)?;
+        let size = size_estimate(position, period);
+        Ok(Self {
+            position,
+            period,
+            size: size.norm(),
+            orientation: size.arg().to_degrees(),
+        })
+    }
+
+    /// Searches around `c` for the parameters of `mandelbrot`, up to its iteration limit.
+    pub fn find_near(mandelbrot: &Mandelbrot, c: Complex64, radius: f64) -> Result<Self, NucleusError> {
+        if mandelbrot.julia.is_some() {
+            return Err(NucleusError::Julia);
+        }
+        Self::find(c, radius, mandelbrot.max_iterations)
+    }
+
+    /// Parameters framing the minibrot upright, the way the default view frames the whole set.
+    /// Everything except the view is taken from `base`. Minibrots smaller than about 1e-6
+    /// are beyond the f32 precision of the renderer and come out blocky.
+    pub fn view(&self, base: &Mandelbrot) -> Mandelbrot {
+        let default = Mandelbrot::default();
+        let size = Complex64::from_polar(self.size, self.orientation.to_radians());
+        let offset = size * Complex64::new(default.position.x as f64, default.position.y as f64);
+        let center = self.position + offset;
+        Mandelbrot {
+            position: Vector::new(center.re as f32, center.im as f32),
+            scale: default.scale * self.size as f32,
+            transform: ViewTransform {
+                rotation: self.orientation as f32,
+                ..Default::default()
+            },
+            // The minibrot needs as many iterations beyond its period as the whole set.
+            max_iterations: base.max_iterations.max(self.period * 100),
+            ..base.clone()
+        }
+    }
+}
+
+/// Ball period method: follows a disk of parameters around `center` and
+/// returns the first iteration at which its image contains 0.
+pub fn find_period(center: Complex64, radius: f64, max_period: usize) -> Option<usize> {
+    let mut z = Complex64::new(0.0, 0.0);
+    let mut r = 0.0;
+    for period in 1..=max_period {
+        // |z(c) - z| grows at most by r² + 2|z|r, plus the radius of the parameter disk.
+        r = r * r + 2.0 * z.norm() * r + radius;
+        z = z * z + center;
+        if z.norm() < r {
+            return Some(period);
+        }
+        if !r.is_finite() || r > 1e10 {
+            return None;
+        }
+    }
+    None
+}
+
+/// Newton iteration on z_period(c) = 0, starting at `guess`.
+pub fn refine(guess: Complex64, period: usize) -> Option<Complex64> {
+    let mut c = guess;
+    for _ in 0..NEWTON_STEPS {
+        let mut z = Complex64::new(0.0, 0.0);
+        let mut dz = Complex64::new(0.0, 0.0);
+        for _ in 0..period {
+            dz = 2.0 * z * dz + 1.0;
+            z = z * z + c;
+        }
+        let step = z / dz;
+        if !step.is_finite() {
+            return None;
+        }
+        c -= step;
+        if step.norm() <= c.norm().max(1.0) * f64::EPSILON * 4.0 {
+            return Some(c);
+        }
+    }
+    // Rounding can keep the last steps from shrinking any further.
+    Some(c).filter(|c| exact_period(*c, period).is_some())
+}
+
+/// Smallest period dividing `period` for which `nucleus` is within rounding
+/// distance of a root of z_period(c), `None` if there is none.
+pub(crate) fn exact_period(nucleus: Complex64, period: usize) -> Option<usize> {
+    let tolerance = nucleus.norm().max(1.0) * 1e-10;
+    let mut z = Complex64::new(0.0, 0.0);
+    let mut dz = Complex64::new(0.0, 0.0);
+    for iteration in 1..=period {
+        dz = 2.0 * z * dz + 1.0;
+        z = z * z + nucleus;
+        if period.is_multiple_of(iteration) && (z / dz).norm() < tolerance {
+            return Some(iteration);
+        }
+    }
+    None
+}
+
+/// Complex size of the minibrot at `nucleus`: its magnitude relative to the
+/// whole set and its argument the rotation.
+pub fn size_estimate(nucleus: Complex64, period: usize) -> Complex64 {
+    let mut z = Complex64::new(0.0, 0.0);
+    let mut l = Complex64::new(1.0, 0.0);
+    let mut b = Complex64::new(1.0, 0.0);
+    for _ in 1..period {
+        z = z * z + nucleus;
+        l = 2.0 * z * l;
+        b += 1.0 / l;
+    }
+    1.0 / (b * l * l)
+}
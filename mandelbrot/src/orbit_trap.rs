@@ -0,0 +1,175 @@
+use std::fmt;
+
+use num::complex::Complex32;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+use super::rectangle::Rectangle;
+use super::vector::Vector;
+
+#[derive(Debug, Clone, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum TrapShape {
+    Point,
+    Line,
+    Cross,
+    Circle,
+    Image,
+}
+
+#[derive(Debug, Clone, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+#[strum(ascii_case_insensitive)]
+pub enum TrapColoring {
+    Distance,
+    Iteration,
+    Combined,
+}
+
+#[derive(Debug)]
+pub enum TrapImageError {
+    /// The image has no texels.
+    Empty,
+    /// The pixels are not `width`×`height` RGBA8 texels.
+    Size { width: usize, height: usize, len: usize },
+    #[cfg(feature = "image")]
+    Image(image::ImageError),
+}
+
+impl fmt::Display for TrapImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("trap image is empty"),
+            Self::Size { width, height, len } => write!(
+                f,
+                "trap image of {width}x{height} needs {} bytes, got {len}",
+                width * height * 4
+            ),
+            #[cfg(feature = "image")]
+            Self::Image(error) => write!(f, "failed to read trap image: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for TrapImageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "image")]
+            Self::Image(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<image::ImageError> for TrapImageError {
+    fn from(error: image::ImageError) -> Self {
+        Self::Image(error)
+    }
+}
+
+/// RGBA8 bitmap placed over `bounds` in the complex plane. Opaque texels are
+/// at distance zero, transparent texels and everything outside are never hit.
+#[derive(Debug, Clone)]
+pub struct TrapImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+    pub bounds: Rectangle,
+}
+
+impl TrapImage {
+    pub fn new(width: usize, height: usize, pixels: Vec<u8>, bounds: Rectangle) -> Result<Self, TrapImageError> {
+        if width == 0 || height == 0 {
+            return Err(TrapImageError::Empty);
+        }
+        if pixels.len() != width * height * 4 {
+            return Err(TrapImageError::Size {
+                width,
+                height,
+                len: pixels.len(),
+            });
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+            bounds,
+        })
+    }
+
+    #[cfg(feature = "image")]
+    pub fn open(path: impl AsRef<std::path::Path>, bounds: Rectangle) -> Result<Self, TrapImageError> {
+        let image = image::open(path)?.into_rgba8();
+        let (width, height) = image.dimensions();
+        Self::new(width as usize, height as usize, image.into_raw(), bounds)
+    }
+
+    fn distance(&self, z: &Complex32) -> f32 {
+        let u = (z.re - self.bounds.start.x) / self.bounds.width();
+        let v = (z.im - self.bounds.start.y) / self.bounds.height();
+        if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+            return f32::INFINITY;
+        }
+        let x = (u * self.width as f32) as usize;
+        let y = (v * self.height as f32) as usize;
+        let alpha = self.pixels[(y * self.width + x) * 4 + 3];
+        if alpha == 0 {
+            f32::INFINITY
+        } else {
+            1.0 - alpha as f32 / 255.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrbitTrap {
+    pub shape: TrapShape,
+    pub center: Vector,
+    pub angle: f32,
+    pub radius: f32,
+    #[serde(skip)]
+    pub image: Option<TrapImage>,
+    pub coloring: TrapColoring,
+    pub falloff: f32,
+}
+
+impl OrbitTrap {
+    pub fn distance(&self, z: &Complex32) -> f32 {
+        let dx = z.re - self.center.x;
+        let dy = z.im - self.center.y;
+        let (sin, cos) = self.angle.sin_cos();
+        match self.shape {
+            TrapShape::Point => f32::hypot(dx, dy),
+            TrapShape::Line => f32::abs(dx * sin - dy * cos),
+            TrapShape::Cross => f32::min(f32::abs(dx * sin - dy * cos), f32::abs(dx * cos + dy * sin)),
+            TrapShape::Circle => f32::abs(f32::hypot(dx, dy) - self.radius),
+            TrapShape::Image => self.image.as_ref().map_or(f32::INFINITY, |image| image.distance(z)),
+        }
+    }
+
+    /// Maps the closest approach and the iteration it happened at to a coloring value in [0, 1].
+    pub fn value(&self, distance: f32, iteration: usize, max_iterations: usize) -> f32 {
+        let distance = (distance / self.falloff).min(1.0);
+        let iteration = iteration as f32 / max_iterations as f32;
+        match self.coloring {
+            TrapColoring::Distance => distance,
+            TrapColoring::Iteration => iteration,
+            TrapColoring::Combined => (distance + iteration) / 2.0,
+        }
+    }
+}
+
+impl Default for OrbitTrap {
+    fn default() -> Self {
+        Self {
+            shape: TrapShape::Cross,
+            center: Vector::new(0.0, 0.0),
+            angle: 0.0,
+            radius: 1.0,
+            image: None,
+            coloring: TrapColoring::Distance,
+            falloff: 0.5,
+        }
+    }
+}
@@ -0,0 +1,661 @@
+use std::fmt;
+use std::path::Path;
+
+use colorgrad::{
+    BasisGradient, CatmullRomGradient, Color, Gradient, GradientBuilder, GradientBuilderError, LinearGradient,
+};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+#[derive(Debug)]
+pub enum PaletteError {
+    Io(std::io::Error),
+    UnknownFormat(String),
+    Parse {
+        line: usize,
+        message: String,
+    },
+    Empty,
+    /// No stop at this index.
+    StopIndex(usize),
+    Gradient(GradientBuilderError),
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to read palette: {error}"),
+            Self::UnknownFormat(name) => write!(f, "unknown palette format: {name}"),
+            Self::Parse { line, message } => write!(f, "line {line}: {message}"),
+            Self::Empty => f.write_str("palette contains no colors"),
+            Self::StopIndex(index) => write!(f, "palette has no stop {index}"),
+            Self::Gradient(error) => write!(f, "invalid gradient: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for PaletteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Gradient(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PaletteError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<GradientBuilderError> for PaletteError {
+    fn from(error: GradientBuilderError) -> Self {
+        Self::Gradient(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString, EnumIter)]
+#[strum(ascii_case_insensitive)]
+pub enum PaletteFormat {
+    /// GIMP gradient
+    Ggr,
+    /// Fractint / UltraFractal color map
+    Map,
+    /// cpt-city / GMT color palette table
+    Cpt,
+    /// cpt-city SVG linear gradient
+    Svg,
+}
+
+impl PaletteFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+type Stops = Vec<(f32, Color)>;
+
+fn error(line: usize, message: impl Into<String>) -> PaletteError {
+    PaletteError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+fn number(line: usize, value: &str) -> Result<f32, PaletteError> {
+    value
+        .parse::<f32>()
+        .map_err(|_| error(line, format!("invalid number '{value}'")))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+pub enum Blend {
+    #[default]
+    Oklab,
+    LinearRgb,
+    LCH,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Display, EnumString, EnumIter, Serialize, Deserialize)]
+pub enum Interpolation {
+    #[default]
+    CatmullRom,
+    Basis,
+    Linear,
+}
+
+#[derive(Debug, Clone)]
+enum PaletteGradient {
+    CatmullRom(CatmullRomGradient),
+    Basis(BasisGradient),
+    Linear(LinearGradient),
+}
+
+/// Named gradient with editable stops. Stop positions are kept sorted in [0, 1]
+/// and the gradient is rebuilt whenever the stops or the modes change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "PaletteData", into = "PaletteData")]
+pub struct Palette {
+    pub name: String,
+    stops: Stops,
+    blend: Blend,
+    interpolation: Interpolation,
+    gradient: PaletteGradient,
+}
+
+/// Serialized form of a palette, the gradient is rebuilt from the stops on load.
+#[derive(Serialize, Deserialize)]
+struct PaletteData {
+    name: String,
+    stops: Vec<StopData>,
+    #[serde(default)]
+    blend: Blend,
+    #[serde(default)]
+    interpolation: Interpolation,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StopData {
+    position: f32,
+    color: String,
+}
+
+impl TryFrom<PaletteData> for Palette {
+    type Error = PaletteError;
+
+    fn try_from(data: PaletteData) -> Result<Self, Self::Error> {
+        let stops = data
+            .stops
+            .into_iter()
+            .map(|stop| {
+                Color::from_html(&stop.color)
+                    .map(|color| (stop.position, color))
+                    .map_err(|_| GradientBuilderError::InvalidHtmlColors(vec![stop.color]).into())
+            })
+            .collect::<Result<Stops, PaletteError>>()?;
+        Self::new(data.name, stops, data.blend, data.interpolation)
+    }
+}
+
+impl From<Palette> for PaletteData {
+    fn from(palette: Palette) -> Self {
+        Self {
+            stops: palette
+                .stops
+                .iter()
+                .map(|(position, color)| StopData {
+                    position: *position,
+                    color: color.to_css_hex(),
+                })
+                .collect(),
+            name: palette.name,
+            blend: palette.blend,
+            interpolation: palette.interpolation,
+        }
+    }
+}
+
+impl Palette {
+    /// Creates a palette from stops at arbitrary positions, normalizing them to [0, 1].
+    pub fn new(
+        name: impl Into<String>,
+        mut stops: Stops,
+        blend: Blend,
+        interpolation: Interpolation,
+    ) -> Result<Self, PaletteError> {
+        if stops.is_empty() {
+            return Err(PaletteError::Empty);
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let min = stops[0].0;
+        let max = stops[stops.len() - 1].0;
+        let range = if max > min { max - min } else { 1.0 };
+        for (position, _) in &mut stops {
+            *position = (*position - min) / range;
+        }
+        let gradient = Self::build(&stops, blend, interpolation)?;
+        Ok(Self {
+            name: name.into(),
+            stops,
+            blend,
+            interpolation,
+            gradient,
+        })
+    }
+
+    pub fn from_html(name: impl Into<String>, colors: &[&str]) -> Result<Self, PaletteError> {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        let stops = colors
+            .iter()
+            .enumerate()
+            .map(|(index, color)| {
+                Color::from_html(color)
+                    .map(|color| (index as f32 / last, color))
+                    .map_err(|_| GradientBuilderError::InvalidHtmlColors(vec![color.to_string()]).into())
+            })
+            .collect::<Result<Stops, PaletteError>>()?;
+        Self::new(name, stops, Blend::Oklab, Interpolation::CatmullRom)
+    }
+
+    fn build(
+        stops: &[(f32, Color)],
+        blend: Blend,
+        interpolation: Interpolation,
+    ) -> Result<PaletteGradient, PaletteError> {
+        // colorgrad has no LCH blending, so LCH segments are pre-sampled and blended linearly in RGB.
+        const LCH_STEPS: usize = 16;
+        let (positions, colors): (Vec<f32>, Vec<Color>) = match blend {
+            Blend::LCH => stops
+                .windows(2)
+                .flat_map(|pair| {
+                    let ((start, from), (end, to)) = (&pair[0], &pair[1]);
+                    (0..LCH_STEPS).map(move |step| {
+                        let t = step as f32 / LCH_STEPS as f32;
+                        (start + (end - start) * t, from.interpolate_lch(to, t))
+                    })
+                })
+                .chain(stops.last().cloned())
+                .unzip(),
+            _ => stops.iter().cloned().unzip(),
+        };
+        let mut builder = GradientBuilder::new();
+        builder.colors(&colors).domain(&positions).mode(match blend {
+            Blend::Oklab => colorgrad::BlendMode::Oklab,
+            Blend::LinearRgb => colorgrad::BlendMode::LinearRgb,
+            Blend::LCH => colorgrad::BlendMode::Rgb,
+        });
+        Ok(match interpolation {
+            Interpolation::CatmullRom => PaletteGradient::CatmullRom(builder.build()?),
+            Interpolation::Basis => PaletteGradient::Basis(builder.build()?),
+            Interpolation::Linear => PaletteGradient::Linear(builder.build()?),
+        })
+    }
+
+    fn rebuild(&mut self) -> Result<(), PaletteError> {
+        self.gradient = Self::build(&self.stops, self.blend, self.interpolation)?;
+        Ok(())
+    }
+
+    pub fn at(&self, t: f32) -> Color {
+        match &self.gradient {
+            PaletteGradient::CatmullRom(gradient) => gradient.at(t),
+            PaletteGradient::Basis(gradient) => gradient.at(t),
+            PaletteGradient::Linear(gradient) => gradient.at(t),
+        }
+    }
+
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    pub fn blend(&self) -> Blend {
+        self.blend
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Replaces all stops. Positions are clamped to [0, 1] and sorted.
+    pub fn set_stops(&mut self, mut stops: Stops) -> Result<(), PaletteError> {
+        if stops.is_empty() {
+            return Err(PaletteError::Empty);
+        }
+        for (position, _) in &mut stops {
+            *position = position.clamp(0.0, 1.0);
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let gradient = Self::build(&stops, self.blend, self.interpolation)?;
+        self.stops = stops;
+        self.gradient = gradient;
+        Ok(())
+    }
+
+    /// Moves and recolors the stop at `index`, returning its index after re-sorting.
+    pub fn set_stop(&mut self, index: usize, position: f32, color: Color) -> Result<usize, PaletteError> {
+        if index >= self.stops.len() {
+            return Err(PaletteError::StopIndex(index));
+        }
+        let mut stops = self.stops.clone();
+        stops.remove(index);
+        let index = self.insert(&mut stops, position, color);
+        self.set_stops(stops)?;
+        Ok(index)
+    }
+
+    pub fn insert_stop(&mut self, position: f32, color: Color) -> Result<usize, PaletteError> {
+        let mut stops = self.stops.clone();
+        let index = self.insert(&mut stops, position, color);
+        self.set_stops(stops)?;
+        Ok(index)
+    }
+
+    pub fn remove_stop(&mut self, index: usize) -> Result<(f32, Color), PaletteError> {
+        if index >= self.stops.len() {
+            return Err(PaletteError::StopIndex(index));
+        }
+        let mut stops = self.stops.clone();
+        let stop = stops.remove(index);
+        self.set_stops(stops)?;
+        Ok(stop)
+    }
+
+    fn insert(&self, stops: &mut Stops, position: f32, color: Color) -> usize {
+        let position = position.clamp(0.0, 1.0);
+        let index = stops.partition_point(|(existing, _)| *existing <= position);
+        stops.insert(index, (position, color));
+        index
+    }
+
+    pub fn set_blend(&mut self, blend: Blend) -> Result<(), PaletteError> {
+        let previous = std::mem::replace(&mut self.blend, blend);
+        self.rebuild().inspect_err(|_| self.blend = previous)
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) -> Result<(), PaletteError> {
+        let previous = std::mem::replace(&mut self.interpolation, interpolation);
+        self.rebuild().inspect_err(|_| self.interpolation = previous)
+    }
+}
+
+fn parse_ggr(name: &str, source: &str) -> Result<Vec<(String, Stops)>, PaletteError> {
+    let mut lines = source.lines().enumerate().map(|(index, line)| (index + 1, line.trim()));
+    match lines.next() {
+        Some((_, "GIMP Gradient")) => (),
+        Some((line, _)) => return Err(error(line, "missing 'GIMP Gradient' header")),
+        None => return Err(PaletteError::Empty),
+    }
+    let mut name = name.to_string();
+    let (line, count) = loop {
+        match lines.next() {
+            Some((_, text)) if text.starts_with("Name:") => name = text["Name:".len()..].trim().to_string(),
+            Some((line, text)) => break (line, text),
+            None => return Err(PaletteError::Empty),
+        }
+    };
+    let count: usize = count
+        .parse()
+        .map_err(|_| error(line, format!("invalid segment count '{count}'")))?;
+
+    let mut stops = Vec::with_capacity(count * 3);
+    let mut segments = 0;
+    for (line, text) in lines.take(count) {
+        let values = text
+            .split_whitespace()
+            .map(|value| number(line, value))
+            .collect::<Result<Vec<f32>, _>>()?;
+        if values.len() < 11 {
+            return Err(error(line, "segment needs at least 11 values"));
+        }
+        let kind = |index: usize| values.get(index).map_or(0, |value| *value as u8);
+        if kind(13) != 0 || kind(14) != 0 {
+            return Err(error(line, "foreground and background colors are not supported"));
+        }
+        let segment = GgrSegment {
+            left: values[0],
+            middle: values[1],
+            right: values[2],
+            left_color: Color::new(values[3], values[4], values[5], values[6]),
+            right_color: Color::new(values[7], values[8], values[9], values[10]),
+            blending: kind(11),
+            coloring: kind(12),
+        };
+        if segment.blending > 5 {
+            return Err(error(line, format!("unknown blending function {}", segment.blending)));
+        }
+        if segment.coloring > 2 {
+            return Err(error(line, format!("unknown color type {}", segment.coloring)));
+        }
+        segment.push_stops(&mut stops);
+        segments += 1;
+    }
+    if segments < count {
+        return Err(error(source.lines().count(), "unexpected end of file"));
+    }
+    Ok(vec![(name, stops)])
+}
+
+/// One segment of a GIMP gradient, blended from `left` over `middle` to
+/// `right` the way GIMP does.
+struct GgrSegment {
+    left: f32,
+    middle: f32,
+    right: f32,
+    left_color: Color,
+    right_color: Color,
+    /// Linear, curved, sine, sphere increasing, sphere decreasing or step.
+    blending: u8,
+    /// RGB, HSV counterclockwise or HSV clockwise.
+    coloring: u8,
+}
+
+impl GgrSegment {
+    /// Stops per segment when the palette interpolation cannot follow the blending.
+    const SAMPLES: usize = 16;
+
+    fn push_stops(&self, stops: &mut Stops) {
+        match (self.blending, self.coloring) {
+            // Linear RGB segments are linear between the ends and the middle.
+            (0, 0) => stops.extend([
+                (self.left, self.left_color.clone()),
+                (self.middle, self.color_at(self.middle)),
+                (self.right, self.right_color.clone()),
+            ]),
+            // Steps jump at the middle, two stops there keep the edge hard.
+            (5, _) => stops.extend([
+                (self.left, self.left_color.clone()),
+                (self.middle, self.left_color.clone()),
+                (self.middle, self.right_color.clone()),
+                (self.right, self.right_color.clone()),
+            ]),
+            _ => stops.extend((0..=Self::SAMPLES).map(|index| {
+                let position = self.left + (self.right - self.left) * index as f32 / Self::SAMPLES as f32;
+                (position, self.color_at(position))
+            })),
+        }
+    }
+
+    fn color_at(&self, position: f32) -> Color {
+        const EPSILON: f32 = 1e-6;
+        let length = self.right - self.left;
+        let (middle, position) = if length < EPSILON {
+            (0.5, 0.5)
+        } else {
+            ((self.middle - self.left) / length, (position - self.left) / length)
+        };
+        let linear = |position: f32| {
+            if position <= middle {
+                if middle < EPSILON { 0.0 } else { 0.5 * position / middle }
+            } else if 1.0 - middle < EPSILON {
+                1.0
+            } else {
+                0.5 + 0.5 * (position - middle) / (1.0 - middle)
+            }
+        };
+        let factor = match self.blending {
+            1 => position.powf(0.5f32.ln() / middle.max(EPSILON).ln()),
+            2 => ((std::f32::consts::PI * (linear(position) - 0.5)).sin() + 1.0) / 2.0,
+            3 => (1.0 - (linear(position) - 1.0).powi(2)).sqrt(),
+            4 => 1.0 - (1.0 - linear(position).powi(2)).sqrt(),
+            5 => (position >= middle) as u8 as f32,
+            _ => linear(position),
+        }
+        .clamp(0.0, 1.0);
+        let (left, right) = (&self.left_color, &self.right_color);
+        if self.coloring == 0 {
+            return left.interpolate_rgb(right, factor);
+        }
+        let [left_hue, left_saturation, left_value, _] = left.to_hsva();
+        let [right_hue, right_saturation, right_value, _] = right.to_hsva();
+        // Counterclockwise hues increase, clockwise ones decrease, wrapping around if needed.
+        let hue = match self.coloring {
+            1 if left_hue < right_hue => left_hue + (right_hue - left_hue) * factor,
+            1 => left_hue + (360.0 - (left_hue - right_hue)) * factor,
+            _ if right_hue < left_hue => left_hue - (left_hue - right_hue) * factor,
+            _ => left_hue - (360.0 - (right_hue - left_hue)) * factor,
+        };
+        Color::from_hsva(
+            hue.rem_euclid(360.0),
+            left_saturation + (right_saturation - left_saturation) * factor,
+            left_value + (right_value - left_value) * factor,
+            left.a + (right.a - left.a) * factor,
+        )
+    }
+}
+
+fn parse_map(name: &str, source: &str) -> Result<Vec<(String, Stops)>, PaletteError> {
+    let mut colors = Vec::new();
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let mut values = text.split_whitespace();
+        let Some(first) = values.next() else {
+            continue;
+        };
+        if first.starts_with(';') || first.starts_with('#') {
+            continue;
+        }
+        let channel = |value: Option<&str>| -> Result<u8, PaletteError> {
+            let value = value.ok_or_else(|| error(line, "expected three color components"))?;
+            value
+                .parse::<u8>()
+                .map_err(|_| error(line, format!("invalid color component '{value}'")))
+        };
+        let r = channel(Some(first))?;
+        let g = channel(values.next())?;
+        let b = channel(values.next())?;
+        colors.push(Color::from_rgba8(r, g, b, 255));
+    }
+    let last = colors.len().saturating_sub(1).max(1) as f32;
+    let stops = colors
+        .into_iter()
+        .enumerate()
+        .map(|(index, color)| (index as f32 / last, color))
+        .collect();
+    Ok(vec![(name.to_string(), stops)])
+}
+
+fn parse_cpt(name: &str, source: &str) -> Result<Vec<(String, Stops)>, PaletteError> {
+    let mut hsv = false;
+    let mut stops = Vec::new();
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let text = text.trim();
+        if let Some(comment) = text.strip_prefix('#') {
+            if let Some((key, value)) = comment.split_once('=')
+                && key.trim() == "COLOR_MODEL"
+            {
+                hsv = match value.trim().to_ascii_uppercase().as_str() {
+                    "RGB" | "+RGB" => false,
+                    "HSV" | "+HSV" => true,
+                    model => return Err(error(line, format!("unsupported color model '{model}'"))),
+                };
+            }
+            continue;
+        }
+        if text.is_empty() || text.starts_with(['B', 'F', 'N']) {
+            continue;
+        }
+        let values: Vec<&str> = text
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .filter(|value| !value.is_empty())
+            .collect();
+        if values.len() < 8 {
+            return Err(error(line, "segment needs two positions and two colors"));
+        }
+        for values in [&values[0..4], &values[4..8]] {
+            let position = number(line, values[0])?;
+            let [a, b, c] = [
+                number(line, values[1])?,
+                number(line, values[2])?,
+                number(line, values[3])?,
+            ];
+            let color = if hsv {
+                Color::from_hsva(a, b, c, 1.0)
+            } else {
+                Color::from_rgba8(a as u8, b as u8, c as u8, 255)
+            };
+            stops.push((position, color));
+        }
+    }
+    Ok(vec![(name.to_string(), stops)])
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(start) = rest.find(name) {
+        let after = &rest[start + name.len()..];
+        let preceded = rest[..start].ends_with(|c: char| c.is_whitespace());
+        if preceded && let Some(after) = after.trim_start().strip_prefix('=') {
+            let after = after.trim_start();
+            let quote = after.chars().next()?;
+            let value = &after[1..];
+            return value.find(quote).map(|end| &value[..end]);
+        }
+        rest = after;
+    }
+    None
+}
+
+fn style<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    attribute(tag, "style")?
+        .split(';')
+        .filter_map(|declaration| declaration.split_once(':'))
+        .find(|(key, _)| key.trim() == name)
+        .map(|(_, value)| value.trim())
+}
+
+fn parse_svg(name: &str, source: &str) -> Result<Vec<(String, Stops)>, PaletteError> {
+    let line_of = |offset: usize| source[..offset].matches('\n').count() + 1;
+    let mut gradients = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = source[offset..].find("<linearGradient") {
+        let start = offset + start;
+        let end = source[start..]
+            .find("</linearGradient>")
+            .map(|end| start + end)
+            .ok_or_else(|| error(line_of(start), "unterminated linearGradient"))?;
+        let header_end = source[start..end].find('>').map_or(end, |index| start + index);
+        let id = attribute(&source[start..header_end], "id").unwrap_or(name);
+
+        let mut stops = Vec::new();
+        let mut position = start;
+        while let Some(stop) = source[position..end].find("<stop") {
+            let stop = position + stop;
+            let stop_end = source[stop..end]
+                .find('>')
+                .map(|index| stop + index)
+                .ok_or_else(|| error(line_of(stop), "unterminated stop"))?;
+            let tag = &source[stop..stop_end];
+            let line = line_of(stop);
+
+            let offset = attribute(tag, "offset").ok_or_else(|| error(line, "stop without offset"))?;
+            let offset = match offset.strip_suffix('%') {
+                Some(percent) => number(line, percent)? / 100.0,
+                None => number(line, offset)?,
+            };
+            let color = attribute(tag, "stop-color")
+                .or_else(|| style(tag, "stop-color"))
+                .ok_or_else(|| error(line, "stop without stop-color"))?;
+            let mut color =
+                Color::from_html(color).map_err(|_| error(line, format!("invalid stop-color '{color}'")))?;
+            if let Some(opacity) = attribute(tag, "stop-opacity").or_else(|| style(tag, "stop-opacity")) {
+                color.a = number(line, opacity)?;
+            }
+            stops.push((offset, color));
+            position = stop_end;
+        }
+        gradients.push((id.to_string(), stops));
+        offset = end;
+    }
+    if gradients.is_empty() {
+        return Err(PaletteError::Empty);
+    }
+    Ok(gradients)
+}
+
+/// Parses every gradient in `source`. `name` is used for formats that do not carry their own names.
+pub fn parse(format: PaletteFormat, name: &str, source: &str) -> Result<Vec<Palette>, PaletteError> {
+    let gradients = match format {
+        PaletteFormat::Ggr => parse_ggr(name, source)?,
+        PaletteFormat::Map => parse_map(name, source)?,
+        PaletteFormat::Cpt => parse_cpt(name, source)?,
+        PaletteFormat::Svg => parse_svg(name, source)?,
+    };
+    gradients
+        .into_iter()
+        .map(|(name, stops)| Palette::new(name, stops, Blend::Oklab, Interpolation::CatmullRom))
+        .collect()
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Palette>, PaletteError> {
+    let path = path.as_ref();
+    let format =
+        PaletteFormat::from_path(path).ok_or_else(|| PaletteError::UnknownFormat(path.display().to_string()))?;
+    let name = path
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    let source = std::fs::read_to_string(path)?;
+    parse(format, &name, &source)
+}
@@ -0,0 +1,338 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, EnumString};
+
+use super::mandelbrot::{Coloring, Mandelbrot};
+use super::palette::{Blend, Interpolation, Palette};
+use super::vector::Vector;
+
+/// Keyword of the PNG iTXt chunk that carries the parameters as JSON.
+pub const PNG_KEYWORD: &str = "rsfractal";
+
+/// Version written by `save`. Files without a version are read as the legacy `config.json` layout.
+/// Version 1 files describe the view with independent half extents instead of a scale.
+pub const VERSION: u32 = 2;
+
+#[derive(Debug)]
+pub enum ParameterError {
+    Io(std::io::Error),
+    UnknownFormat(String),
+    Json(serde_json::Error),
+    Toml(String),
+    Png(String),
+    Missing,
+    Invalid(String),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to access parameter file: {error}"),
+            Self::UnknownFormat(name) => write!(f, "unknown parameter format: {name}"),
+            Self::Json(error) => write!(f, "invalid JSON parameters: {error}"),
+            Self::Toml(error) => write!(f, "invalid TOML parameters: {error}"),
+            Self::Png(error) => write!(f, "invalid PNG: {error}"),
+            Self::Missing => f.write_str("image contains no rsfractal parameters"),
+            Self::Invalid(message) => write!(f, "invalid parameters: {message}"),
+            Self::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "unsupported parameter file version {version}, expected 1 to {VERSION}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParameterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ParameterError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ParameterError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumString, EnumIter)]
+#[strum(ascii_case_insensitive)]
+pub enum ParameterFormat {
+    Json,
+    Toml,
+    /// Rendered image with the parameters in an iTXt chunk
+    Png,
+}
+
+impl ParameterFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+/// On-disk parameter set. Missing fields take their default values and
+/// unknown fields are ignored, so files stay loadable across versions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterFile {
+    pub version: u32,
+    /// Half width and half height of the view, replaced by `scale` in version 2.
+    #[serde(default, skip_serializing)]
+    pub zoom: Option<Vector>,
+    #[serde(flatten)]
+    pub mandelbrot: Mandelbrot,
+}
+
+impl ParameterFile {
+    /// Rejects versions this build does not know, newer files may mean their fields differently.
+    pub(crate) fn check_version(version: u32) -> Result<(), ParameterError> {
+        if (1..=VERSION).contains(&version) {
+            Ok(())
+        } else {
+            Err(ParameterError::UnsupportedVersion(version))
+        }
+    }
+
+    pub fn into_mandelbrot(self) -> Mandelbrot {
+        let mut mandelbrot = self.mandelbrot;
+        if let Some(zoom) = self.zoom {
+            mandelbrot.fit_extent(&zoom);
+        }
+        mandelbrot
+    }
+}
+
+#[derive(Deserialize)]
+struct Versioned {
+    version: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct LegacyConfig {
+    width: usize,
+    height: usize,
+    position: Vector,
+    zoom: Vector,
+    iterations: usize,
+    chunk_size: usize,
+    palette: Vec<LegacyColor>,
+}
+
+#[derive(Deserialize)]
+struct LegacyColor {
+    r: u8,
+    g: u8,
+    b: u8,
+}
+
+impl LegacyConfig {
+    fn into_mandelbrot(self) -> Result<Mandelbrot, ParameterError> {
+        let last = self.palette.len().saturating_sub(1).max(1) as f32;
+        let stops = self
+            .palette
+            .iter()
+            .enumerate()
+            .map(|(index, color)| {
+                (
+                    index as f32 / last,
+                    colorgrad::Color::from_rgba8(color.r, color.g, color.b, 255),
+                )
+            })
+            .collect();
+        let palette = Palette::new("Config", stops, Blend::default(), Interpolation::default())
+            .map_err(|error| ParameterError::Invalid(error.to_string()))?;
+
+        let mut mandelbrot = Mandelbrot {
+            width: self.width,
+            height: self.height,
+            position: self.position,
+            max_iterations: self.iterations,
+            chunk_size: self.chunk_size,
+            coloring: Coloring::Palette,
+            ..Default::default()
+        };
+        mandelbrot.fit_extent(&self.zoom);
+        mandelbrot.selected_palette = mandelbrot.insert_palette(palette);
+        Ok(mandelbrot)
+    }
+}
+
+impl Mandelbrot {
+    pub fn from_json(source: &str) -> Result<Self, ParameterError> {
+        let versioned: Versioned = serde_json::from_str(source)?;
+        let mandelbrot = match versioned.version {
+            Some(version) => {
+                ParameterFile::check_version(version)?;
+                serde_json::from_str::<ParameterFile>(source)?.into_mandelbrot()
+            }
+            None => serde_json::from_str::<LegacyConfig>(source)?.into_mandelbrot()?,
+        };
+        mandelbrot.validated()
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, ParameterError> {
+        let file: ParameterFile = toml::from_str(source).map_err(|error| ParameterError::Toml(error.to_string()))?;
+        ParameterFile::check_version(file.version)?;
+        file.into_mandelbrot().validated()
+    }
+
+    pub fn to_json(&self) -> Result<String, ParameterError> {
+        Ok(serde_json::to_string_pretty(&self.parameter_file())?)
+    }
+
+    pub fn to_toml(&self) -> Result<String, ParameterError> {
+        toml::to_string_pretty(&self.parameter_file()).map_err(|error| ParameterError::Toml(error.to_string()))
+    }
+
+    /// Reads parameters from `bytes`, for PNG images from their embedded metadata.
+    pub fn decode(format: ParameterFormat, bytes: &[u8]) -> Result<Self, ParameterError> {
+        match format {
+            ParameterFormat::Json => Self::from_json(&Self::utf8(bytes)?),
+            ParameterFormat::Toml => Self::from_toml(&Self::utf8(bytes)?),
+            ParameterFormat::Png => Self::from_png(bytes),
+        }
+    }
+
+    /// Serializes the parameters, PNG output renders the current view and embeds them.
+    pub fn encode(&self, format: ParameterFormat) -> Result<Vec<u8>, ParameterError> {
+        match format {
+            ParameterFormat::Json => Ok(self.to_json()?.into_bytes()),
+            ParameterFormat::Toml => Ok(self.to_toml()?.into_bytes()),
+            ParameterFormat::Png => {
+                let mut pixels = vec![0u8; self.width * self.height * 4];
+                self.render(&mut pixels);
+                self.encode_png(&pixels)
+            }
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ParameterError> {
+        let path = path.as_ref();
+        Self::decode(Self::parameter_format(path)?, &std::fs::read(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ParameterError> {
+        let path = path.as_ref();
+        let bytes = self.encode(Self::parameter_format(path)?)?;
+        Ok(std::fs::write(path, bytes)?)
+    }
+
+    /// Encodes RGBA8 `pixels` of the current resolution as a PNG carrying the parameters.
+    pub fn encode_png(&self, pixels: &[u8]) -> Result<Vec<u8>, ParameterError> {
+        let png_error = |error: png::EncodingError| ParameterError::Png(error.to_string());
+        let parameters = serde_json::to_string(&self.parameter_file())?;
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .add_itxt_chunk(PNG_KEYWORD.to_string(), parameters)
+            .map_err(png_error)?;
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        writer.write_image_data(pixels).map_err(png_error)?;
+        writer.finish().map_err(png_error)?;
+        Ok(bytes)
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>, pixels: &[u8]) -> Result<(), ParameterError> {
+        Ok(std::fs::write(path, self.encode_png(pixels)?)?)
+    }
+
+    /// Restores the parameters embedded by `encode_png`, the pixels are ignored.
+    pub fn from_png(bytes: &[u8]) -> Result<Self, ParameterError> {
+        let png_error = |error: png::DecodingError| ParameterError::Png(error.to_string());
+        // Text chunks may also follow the image data, so every chunk is read
+        // while the image data is skipped without inflating it.
+        let mut decoder = png::StreamingDecoder::new();
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let (consumed, decoded) = decoder.update(rest, None).map_err(png_error)?;
+            rest = &rest[consumed..];
+            if consumed == 0 || matches!(decoded, png::Decoded::ChunkComplete(png::chunk::IEND)) {
+                break;
+            }
+        }
+        let info = decoder.info().ok_or(ParameterError::Missing)?;
+        let text = match info.utf8_text.iter().find(|chunk| chunk.keyword == PNG_KEYWORD) {
+            Some(chunk) => chunk.get_text().map_err(png_error)?,
+            None => info
+                .uncompressed_latin1_text
+                .iter()
+                .find(|chunk| chunk.keyword == PNG_KEYWORD)
+                .map(|chunk| chunk.text.clone())
+                .ok_or(ParameterError::Missing)?,
+        };
+        Self::from_json(&text)
+    }
+
+    fn utf8(bytes: &[u8]) -> Result<String, ParameterError> {
+        String::from_utf8(bytes.to_vec()).map_err(|error| ParameterError::Invalid(error.to_string()))
+    }
+
+    fn parameter_format(path: &Path) -> Result<ParameterFormat, ParameterError> {
+        ParameterFormat::from_path(path).ok_or_else(|| ParameterError::UnknownFormat(path.display().to_string()))
+    }
+
+    fn parameter_file(&self) -> ParameterFile {
+        ParameterFile {
+            version: VERSION,
+            zoom: None,
+            mandelbrot: self.clone(),
+        }
+    }
+
+    /// Rejects values the renderer cannot work with.
+    pub fn validated(self) -> Result<Self, ParameterError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(ParameterError::Invalid("resolution must not be zero".to_string()));
+        }
+        if self.max_iterations == 0 || self.chunk_size == 0 {
+            return Err(ParameterError::Invalid(
+                "iterations and chunk size must not be zero".to_string(),
+            ));
+        }
+        if !(self.scale.is_finite() && self.scale > 0.0) {
+            return Err(ParameterError::Invalid("scale must be positive".to_string()));
+        }
+        if !(self.bailout.is_finite() && self.bailout > 0.0) {
+            return Err(ParameterError::Invalid("bailout must be positive".to_string()));
+        }
+        if self.antialiasing.samples == 0 {
+            return Err(ParameterError::Invalid(
+                "antialiasing samples must not be zero".to_string(),
+            ));
+        }
+        if !(self.lighting.height.is_finite() && self.lighting.height > 0.0) {
+            return Err(ParameterError::Invalid("lighting height must be positive".to_string()));
+        }
+        if !self.transform.rotation.is_finite() || !self.transform.skew.is_finite() {
+            return Err(ParameterError::Invalid("rotation and skew must be finite".to_string()));
+        }
+        if !(self.trap.falloff.is_finite() && self.trap.falloff > 0.0) {
+            return Err(ParameterError::Invalid("trap falloff must be positive".to_string()));
+        }
+        if self.palettes().is_empty() {
+            return Err(ParameterError::Invalid("at least one palette is required".to_string()));
+        }
+        if self.selected_palette >= self.palettes().len() {
+            return Err(ParameterError::Invalid(format!(
+                "selected palette {} does not exist",
+                self.selected_palette
+            )));
+        }
+        Ok(self)
+    }
+}
@@ -0,0 +1,40 @@
+use std::ops::Range;
+
+use super::mandelbrot::Mandelbrot;
+
+/// Renders a view in horizontal bands of full width, so images far larger
+/// than memory can be streamed to an encoder one band at a time.
+#[derive(Debug, Clone)]
+pub struct Poster {
+    pub mandelbrot: Mandelbrot,
+    pub band_height: usize,
+}
+
+impl Poster {
+    pub fn new(mandelbrot: Mandelbrot, band_height: usize) -> Self {
+        Self {
+            mandelbrot,
+            band_height: band_height.max(1),
+        }
+    }
+
+    pub fn band_count(&self) -> usize {
+        self.mandelbrot.height.div_ceil(self.band_height)
+    }
+
+    /// Rows of the full image covered by band `index`, the last band may be shorter.
+    pub fn band_rows(&self, index: usize) -> Range<usize> {
+        let start = (index * self.band_height).min(self.mandelbrot.height);
+        start..(start + self.band_height).min(self.mandelbrot.height)
+    }
+
+    /// Renders band `index` as RGBA8 into `pixels`, which is resized to fit.
+    /// The pixels are identical to the same rows of a full render, except in
+    /// fast rendering, which traces each band on its own and may miss shapes
+    /// that do not reach the edge of the band.
+    pub fn render_band(&self, index: usize, pixels: &mut Vec<u8>) {
+        let rows = self.band_rows(index);
+        pixels.resize(self.mandelbrot.width * rows.len() * 4, 0);
+        self.mandelbrot.render_rows(rows, pixels);
+    }
+}
@@ -0,0 +1,509 @@
+use std::f64::consts::TAU;
+use std::fmt;
+use std::str::FromStr;
+
+use num::complex::Complex64;
+
+use super::mandelbrot::Mandelbrot;
+use super::misiurewicz::Misiurewicz;
+use super::nucleus::{self, Nucleus};
+
+/// Longest binary expansion, preperiod plus period, that fits the denominator.
+const MAX_BITS: u32 = 62;
+/// Newton steps per ray point.
+const NEWTON_STEPS: usize = 64;
+/// Steps of the multiplier from a nucleus to the root of its component.
+const ROOT_STEPS: usize = 16;
+/// Exterior points are searched on this many steps to either side of a line from the root.
+const ROOT_SAMPLES: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RayError {
+    /// The angle is not of the form p/q or .pre(period) with binary digits.
+    Syntax(String),
+    /// The binary expansion is longer than 62 digits.
+    TooLong,
+    /// The landing point could not be refined.
+    NotConverged,
+}
+
+impl fmt::Display for RayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Syntax(angle) => write!(
+                f,
+                "invalid angle '{angle}', expected p/q or a binary expansion like .01(10)"
+            ),
+            Self::TooLong => write!(f, "angles are limited to {MAX_BITS} binary digits"),
+            Self::NotConverged => f.write_str("landing point did not converge"),
+        }
+    }
+}
+
+impl std::error::Error for RayError {}
+
+/// Rational external angle in turns, `numerator / denominator` in [0, 1) and reduced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Angle {
+    numerator: u64,
+    denominator: u64,
+}
+
+impl Angle {
+    pub fn new(numerator: u64, denominator: u64) -> Result<Self, RayError> {
+        if denominator == 0 {
+            return Err(RayError::Syntax(format!("{numerator}/0")));
+        }
+        let numerator = numerator % denominator;
+        let divisor = gcd(numerator, denominator);
+        let denominator = denominator / divisor;
+        // Doubling only ever divides the denominator, so this bounds every later expansion.
+        orbit_type(denominator).ok_or(RayError::TooLong)?;
+        Ok(Self {
+            numerator: numerator / divisor,
+            denominator,
+        })
+    }
+
+    /// Angle with the binary expansion `.preperiodic(periodic)`.
+    pub fn from_bits(preperiodic: &[bool], periodic: &[bool]) -> Result<Self, RayError> {
+        let (k, n) = (preperiodic.len() as u32, periodic.len() as u32);
+        if k + n > MAX_BITS {
+            return Err(RayError::TooLong);
+        }
+        let value = |bits: &[bool]| bits.iter().fold(0u64, |value, bit| value << 1 | *bit as u64);
+        if n == 0 {
+            return Self::new(value(preperiodic), 1 << k);
+        }
+        let repeat = (1u64 << n) - 1;
+        Self::new(value(preperiodic) * repeat + value(periodic), repeat << k)
+    }
+
+    pub fn numerator(&self) -> u64 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> u64 {
+        self.denominator
+    }
+
+    pub fn turns(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// The angle under z → z², twice the angle modulo 1.
+    pub fn doubled(&self) -> Self {
+        Self {
+            numerator: ((self.numerator as u128 * 2) % self.denominator as u128) as u64,
+            denominator: self.denominator,
+        }
+        .reduced()
+    }
+
+    /// Preperiod and period under doubling. Periodic angles land on the root of
+    /// a component of that period, preperiodic ones on a Misiurewicz point.
+    pub fn orbit_type(&self) -> (usize, usize) {
+        orbit_type(self.denominator).expect("angles are checked on construction")
+    }
+
+    /// Binary expansion with the repeating digits in parentheses, like `.0(01)` for 1/6.
+    pub fn binary(&self) -> String {
+        let (preperiod, period) = self.orbit_type();
+        let mut angle = *self;
+        let mut digits = String::from(".");
+        for index in 0..preperiod + period {
+            if index == preperiod {
+                digits.push('(');
+            }
+            digits.push(if angle.numerator * 2 >= angle.denominator {
+                '1'
+            } else {
+                '0'
+            });
+            angle = angle.doubled();
+        }
+        digits.push(')');
+        digits
+    }
+
+    fn reduced(self) -> Self {
+        let divisor = gcd(self.numerator, self.denominator);
+        Self {
+            numerator: self.numerator / divisor,
+            denominator: self.denominator / divisor,
+        }
+    }
+}
+
+impl fmt::Display for Angle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+impl FromStr for Angle {
+    type Err = RayError;
+
+    /// Parses `p/q`, a whole number of turns or a binary expansion like `.01(10)`, `0.(011)` or `.1`.
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let syntax = || RayError::Syntax(source.to_string());
+        let trimmed = source.trim();
+        if let Ok(turns) = trimmed.parse::<u64>() {
+            return Self::new(turns, 1);
+        }
+        if let Some((numerator, denominator)) = trimmed.split_once('/') {
+            let parse = |part: &str| part.trim().parse::<u64>().map_err(|_| syntax());
+            return Self::new(parse(numerator)?, parse(denominator)?);
+        }
+        let digits = trimmed
+            .strip_prefix('0')
+            .unwrap_or(trimmed)
+            .strip_prefix('.')
+            .ok_or_else(syntax)?;
+        let (preperiodic, periodic) = match digits.split_once('(') {
+            Some((preperiodic, periodic)) => (preperiodic, periodic.strip_suffix(')').ok_or_else(syntax)?),
+            None => (digits, ""),
+        };
+        let bits = |part: &str| {
+            part.chars()
+                .map(|digit| match digit {
+                    '0' => Ok(false),
+                    '1' => Ok(true),
+                    _ => Err(syntax()),
+                })
+                .collect::<Result<Vec<bool>, RayError>>()
+        };
+        Self::from_bits(&bits(preperiodic)?, &bits(periodic)?)
+    }
+}
+
+/// Preperiod and period of the angles with `denominator`, `None` if they
+/// take more than `MAX_BITS` binary digits.
+fn orbit_type(denominator: u64) -> Option<(usize, usize)> {
+    let preperiod = denominator.trailing_zeros() as usize;
+    let odd = denominator >> preperiod;
+    let mut period = 1;
+    let mut power = 2 % odd;
+    while odd > 1 && power != 1 {
+        if preperiod + period >= MAX_BITS as usize {
+            return None;
+        }
+        power = ((power as u128 * 2) % odd as u128) as u64;
+        period += 1;
+    }
+    (preperiod + period <= MAX_BITS as usize).then_some((preperiod, period))
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+/// Traces external rays of the Mandelbrot set with Newton's method: every
+/// ray point c solves z_n(c) = r·e^(2πi·2^(n-1)·θ) for radii shrinking from
+/// the escape radius towards the set.
+#[derive(Debug, Clone)]
+pub struct RayTracer {
+    /// Ray points per level of the escape time.
+    pub sharpness: usize,
+    pub escape_radius: f64,
+    /// Deepest escape time level a ray is followed to.
+    pub max_depth: usize,
+    /// Tracing stops once a whole level moves the ray less than this.
+    pub precision: f64,
+}
+
+impl Default for RayTracer {
+    fn default() -> Self {
+        Self {
+            sharpness: 8,
+            escape_radius: 65536.0,
+            max_depth: 256,
+            precision: 1e-10,
+        }
+    }
+}
+
+impl RayTracer {
+    /// Points of the ray at `angle`, from the escape radius towards its landing point.
+    pub fn trace(&self, angle: Angle) -> Vec<Complex64> {
+        let sharpness = self.sharpness.max(1);
+        let mut angle = angle;
+        let mut c = Complex64::from_polar(self.escape_radius, TAU * angle.turns());
+        let mut points = vec![c];
+        for depth in 0..self.max_depth {
+            let level_start = c;
+            for step in 0..sharpness {
+                let exponent = 0.5f64.powf((step as f64 + 0.5) / sharpness as f64);
+                let target = Complex64::from_polar(self.escape_radius.powf(exponent), TAU * angle.turns());
+                match newton_ray_step(c, target, depth + 1) {
+                    Some(next) => c = next,
+                    None => return points,
+                }
+                points.push(c);
+            }
+            if (c - level_start).norm() < self.precision {
+                break;
+            }
+            angle = angle.doubled();
+        }
+        points
+    }
+
+    /// Binary digits of the external angle of the exterior point `c`, read while
+    /// tracing its ray outwards. `None` for points that do not escape.
+    pub fn angle_bits(&self, c: Complex64, max_iterations: usize) -> Option<Vec<bool>> {
+        let sharpness = self.sharpness.max(1);
+        let mut z = Complex64::new(0.0, 0.0);
+        let mut level = 0;
+        while z.norm() <= self.escape_radius {
+            if level >= max_iterations {
+                return None;
+            }
+            z = z * z + c;
+            level += 1;
+        }
+        let mut c = c;
+        let mut bits = Vec::with_capacity(level);
+        while level > 0 {
+            for step in 0..sharpness {
+                // Radii grow from the escape radius to its square, where the
+                // previous level reaches the escape radius.
+                let exponent = 2.0f64.powf((step as f64 + 1.0) / sharpness as f64);
+                let target = Complex64::from_polar(self.escape_radius.powf(exponent), z.arg());
+                c = newton_ray_step(c, target, level)?;
+                z = iterate(c, level);
+            }
+            // z_level turned by 2^(level-1) times the angle, its half plane is digit `level`.
+            bits.push(z.im < 0.0);
+            level -= 1;
+            z = iterate(c, level);
+        }
+        bits.reverse();
+        Some(bits)
+    }
+
+    /// External angles of the rays landing on the root of the component of
+    /// `nucleus`, read from exterior points just outside the root.
+    pub fn root_angles(&self, nucleus: &Nucleus, max_iterations: usize) -> Result<Vec<Angle>, RayError> {
+        let root = root(nucleus.position, nucleus.period).ok_or(RayError::NotConverged)?;
+        // Only the main cardioid has a single ray at its root.
+        let expected = if nucleus.period == 1 { 1 } else { 2 };
+        let outward = (root - nucleus.position).unscale((root - nucleus.position).norm());
+        let mut angles = Vec::with_capacity(expected);
+        // Near a cusp the exterior is a thin band along the outward direction and
+        // near the root of a disk it runs sideways, between the disk and its parent.
+        // The bands are scanned across at shrinking distances from the root.
+        let samples = [1e-2, 1e-3].into_iter().flat_map(|distance| {
+            [outward, outward * Complex64::i(), -outward * Complex64::i()]
+                .into_iter()
+                .flat_map(move |axis| {
+                    (0..=ROOT_SAMPLES as i64 * 2)
+                        .map(|step| if step % 2 == 0 { step / 2 } else { -(step + 1) / 2 })
+                        .map(move |step| axis * Complex64::new(1.0, 0.2 * step as f64 / ROOT_SAMPLES as f64))
+                })
+                .map(move |offset| offset * distance * nucleus.size)
+        });
+        // Reading the angle costs quadratically in the escape time, so the
+        // quickest escaping points are traced first.
+        let mut exterior: Vec<(usize, Complex64)> = samples
+            .map(|offset| root + offset)
+            .filter_map(|c| escape_time(c, max_iterations).map(|time| (time, c)))
+            .collect();
+        exterior.sort_by_key(|(time, _)| *time);
+        for (_, c) in exterior {
+            let Some(bits) = self.angle_bits(c, max_iterations) else {
+                continue;
+            };
+            if bits.len() < nucleus.period {
+                continue;
+            }
+            let angle = Angle::from_bits(&[], &bits[..nucleus.period])?;
+            if !angles.contains(&angle) {
+                angles.push(angle);
+                if angles.len() == expected {
+                    break;
+                }
+            }
+        }
+        angles.sort();
+        Ok(angles)
+    }
+}
+
+fn escape_time(c: Complex64, max_iterations: usize) -> Option<usize> {
+    let mut z = Complex64::new(0.0, 0.0);
+    (0..max_iterations).find(|_| {
+        z = z * z + c;
+        z.norm_sqr() > 4.0
+    })
+}
+
+/// Newton iteration on z_level(c) = target.
+fn newton_ray_step(c: Complex64, target: Complex64, level: usize) -> Option<Complex64> {
+    let mut c = c;
+    for _ in 0..NEWTON_STEPS {
+        let (mut z, mut dz) = (Complex64::new(0.0, 0.0), Complex64::new(0.0, 0.0));
+        for _ in 0..level {
+            dz = 2.0 * z * dz + 1.0;
+            z = z * z + c;
+        }
+        let step = (z - target) / dz;
+        if !step.is_finite() {
+            return None;
+        }
+        c -= step;
+        if step.norm() <= c.norm() * f64::EPSILON * 4.0 {
+            break;
+        }
+    }
+    Some(c)
+}
+
+fn iterate(c: Complex64, iterations: usize) -> Complex64 {
+    (0..iterations).fold(Complex64::new(0.0, 0.0), |z, _| z * z + c)
+}
+
+/// Where a ray ends up and what kind of point it is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Landing {
+    /// Root of a hyperbolic component, for periodic angles.
+    Root { position: Complex64, period: usize },
+    /// Misiurewicz point, for preperiodic angles.
+    Misiurewicz(Misiurewicz),
+}
+
+impl Landing {
+    pub fn position(&self) -> Complex64 {
+        match self {
+            Self::Root { position, .. } => *position,
+            Self::Misiurewicz(point) => point.position,
+        }
+    }
+}
+
+/// Estimates the landing point of the ray at `angle` from its traced end and refines it.
+pub fn landing_point(tracer: &RayTracer, angle: Angle) -> Result<Landing, RayError> {
+    let end = *tracer.trace(angle).last().expect("a ray has at least its first point");
+    match angle.orbit_type() {
+        (0, period) => {
+            let nucleus = nucleus::refine(end, period).ok_or(RayError::NotConverged)?;
+            let period = nucleus::exact_period(nucleus, period).ok_or(RayError::NotConverged)?;
+            let position = root(nucleus, period).ok_or(RayError::NotConverged)?;
+            Ok(Landing::Root { position, period })
+        }
+        // The angle turns periodic one step before the critical orbit does.
+        // Several rays of a longer period can land on a point of a shorter one.
+        (preperiod, period) => (1..=period)
+            .filter(|divisor| period.is_multiple_of(*divisor))
+            .filter_map(|divisor| Misiurewicz::find(end, preperiod + 1, divisor).ok())
+            .min_by(|a, b| (a.position - end).norm().total_cmp(&(b.position - end).norm()))
+            .map(Landing::Misiurewicz)
+            .ok_or(RayError::NotConverged),
+    }
+}
+
+/// Root of the component with the given nucleus: the parameter where the
+/// multiplier of its cycle reaches 1, followed from 0 at the nucleus.
+fn root(nucleus: Complex64, period: usize) -> Option<Complex64> {
+    let (mut z, mut c) = (Complex64::new(0.0, 0.0), nucleus);
+    for step in 1..=ROOT_STEPS {
+        let multiplier = Complex64::new(step as f64 / ROOT_STEPS as f64, 0.0);
+        for _ in 0..NEWTON_STEPS {
+            // f^period(z) - z and its derivative minus the multiplier, with their
+            // derivatives by z and c.
+            let (mut w, mut dz, mut dc, mut dzz, mut dcz) = (
+                z,
+                Complex64::new(1.0, 0.0),
+                Complex64::new(0.0, 0.0),
+                Complex64::new(0.0, 0.0),
+                Complex64::new(0.0, 0.0),
+            );
+            for _ in 0..period {
+                dcz = 2.0 * (dc * dz + w * dcz);
+                dzz = 2.0 * (dz * dz + w * dzz);
+                dc = 2.0 * w * dc + 1.0;
+                dz = 2.0 * w * dz;
+                w = w * w + c;
+            }
+            let (f, g) = (w - z, dz - multiplier);
+            let (a, b, d, e) = (dz - 1.0, dc, dzz, dcz);
+            let determinant = a * e - b * d;
+            let step_z = (f * e - b * g) / determinant;
+            let step_c = (a * g - f * d) / determinant;
+            if !step_z.is_finite() || !step_c.is_finite() {
+                return None;
+            }
+            z -= step_z;
+            c -= step_c;
+            if step_c.norm() <= c.norm().max(1.0) * f64::EPSILON * 4.0 {
+                break;
+            }
+        }
+    }
+    Some(c)
+}
+
+/// Draws `points` as connected line segments over RGBA8 `pixels` of the view of `mandelbrot`.
+pub fn draw_polyline(mandelbrot: &Mandelbrot, points: &[Complex64], color: [u8; 4], pixels: &mut [u8]) {
+    let mapping = mandelbrot.mapping();
+    let (width, height) = (mandelbrot.width, mandelbrot.height);
+    let to_pixel = |c: &Complex64| {
+        let (x, y) = mapping.inverse(num::complex::Complex32::new(c.re as f32, c.im as f32));
+        (x as f64, y as f64)
+    };
+    for segment in points.windows(2) {
+        let Some((start, end)) = clip(
+            to_pixel(&segment[0]),
+            to_pixel(&segment[1]),
+            width as f64,
+            height as f64,
+        ) else {
+            continue;
+        };
+        let steps = (end.0 - start.0).abs().max((end.1 - start.1).abs()).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+            let x = (start.0 + (end.0 - start.0) * t) as usize;
+            let y = (start.1 + (end.1 - start.1) * t) as usize;
+            if x < width && y < height {
+                let index = (y * width + x) * 4;
+                pixels[index..index + 4].copy_from_slice(&color);
+            }
+        }
+    }
+}
+
+/// Liang–Barsky clipping of a segment to [0, width]×[0, height].
+fn clip(start: (f64, f64), end: (f64, f64), width: f64, height: f64) -> Option<((f64, f64), (f64, f64))> {
+    if ![start.0, start.1, end.0, end.1].iter().all(|value| value.is_finite()) {
+        return None;
+    }
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let (mut low, mut high) = (0.0f64, 1.0f64);
+    for (p, q) in [
+        (-dx, start.0),
+        (dx, width - start.0),
+        (-dy, start.1),
+        (dy, height - start.1),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                low = low.max(t);
+            } else {
+                high = high.min(t);
+            }
+        }
+    }
+    (low <= high).then_some((
+        (start.0 + dx * low, start.1 + dy * low),
+        (start.0 + dx * high, start.1 + dy * high),
+    ))
+}
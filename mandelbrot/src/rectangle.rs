@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use super::vector::Vector;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rectangle {
     pub start: Vector,
     pub end: Vector,
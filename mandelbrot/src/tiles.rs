@@ -0,0 +1,84 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::mandelbrot::Mandelbrot;
+use super::rectangle::Rectangle;
+use super::transform::ViewTransform;
+use super::vector::Vector;
+
+/// Deepest zoom level served. A pixel of a default 256 pixel tile at level 16 spans
+/// 4 / 2^24 = 2^-22, the spacing of single precision numbers between 2 and 4,
+/// so deeper levels could no longer tell neighbouring pixels at the edge of
+/// the world apart.
+pub const MAX_ZOOM: u32 = 16;
+
+/// The square covered by the single tile of zoom level 0. Row 0 of a tile is
+/// its smallest imaginary part, like the rows of every other render.
+pub const WORLD: Rectangle = Rectangle {
+    start: Vector { x: -2.5, y: -2.0 },
+    end: Vector { x: 1.5, y: 2.0 },
+};
+
+/// Slippy-map tile address, level `z` splits the world into 2^z × 2^z tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileAddress {
+    pub z: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileAddress {
+    pub fn new(z: u32, x: u32, y: u32) -> Option<Self> {
+        (z <= MAX_ZOOM && x < 1 << z && y < 1 << z).then_some(Self { z, x, y })
+    }
+
+    /// Region of the complex plane covered by the tile.
+    pub fn rect(&self) -> Rectangle {
+        let tiles = (1u32 << self.z) as f32;
+        let width = WORLD.width() / tiles;
+        let height = WORLD.height() / tiles;
+        let start = Vector::new(
+            WORLD.start.x + self.x as f32 * width,
+            WORLD.start.y + self.y as f32 * height,
+        );
+        let end = Vector::new(start.x + width, start.y + height);
+        Rectangle::new(start, end)
+    }
+
+    /// Parameters rendering the tile as a `size`×`size` image, everything
+    /// except the view is taken from `base`. Tiles are never rotated.
+    pub fn view(&self, base: &Mandelbrot, size: usize) -> Mandelbrot {
+        let rect = self.rect();
+        let mut mandelbrot = base.clone();
+        mandelbrot.set_resolution(size, size);
+        mandelbrot.transform = ViewTransform::default();
+        mandelbrot.position = Vector::new((rect.start.x + rect.end.x) / 2.0, (rect.start.y + rect.end.y) / 2.0);
+        mandelbrot.scale = rect.height() / 2.0;
+        mandelbrot
+    }
+}
+
+impl fmt::Display for TileAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.z, self.x, self.y)
+    }
+}
+
+/// Parses `z/x/y`, an optional `.png` extension is accepted.
+impl FromStr for TileAddress {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim_matches('/');
+        let value = value.strip_suffix(".png").unwrap_or(value);
+        let parts: Vec<u32> = value
+            .split('/')
+            .map(|part| part.parse::<u32>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("invalid tile address '{value}'"))?;
+        match parts[..] {
+            [z, x, y] => Self::new(z, x, y).ok_or_else(|| format!("tile {value} is out of range")),
+            _ => Err(format!("expected z/x/y but got '{value}'")),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use num::complex::Complex32;
+use serde::{Deserialize, Serialize};
+
+/// Orientation of the view around its center. Offsets from the center are
+/// flipped, then skewed and finally rotated.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewTransform {
+    /// Counterclockwise rotation in degrees.
+    pub rotation: f32,
+    /// Horizontal shear, moves each row by `skew` times its vertical offset.
+    pub skew: f32,
+    /// Mirrors the view horizontally.
+    pub flip: bool,
+}
+
+impl ViewTransform {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Row-major 2×2 matrix mapping view offsets to offsets in the plane.
+    pub fn matrix(&self) -> [[f32; 2]; 2] {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let flip = if self.flip { -1.0 } else { 1.0 };
+        // rotation · [[1, skew], [0, 1]] · [[flip, 0], [0, 1]]
+        [[cos * flip, cos * self.skew - sin], [sin * flip, sin * self.skew + cos]]
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let [[a, b], [c, d]] = self.matrix();
+        (a * x + b * y, c * x + d * y)
+    }
+
+    /// Inverse of `apply`, from offsets in the plane back to view offsets.
+    pub fn invert(&self, x: f32, y: f32) -> (f32, f32) {
+        let [[a, b], [c, d]] = self.matrix();
+        let determinant = a * d - b * c;
+        ((d * x - b * y) / determinant, (a * y - c * x) / determinant)
+    }
+}
+
+/// Affine map from pixel coordinates to the complex plane,
+/// `origin + x · step_x + y · step_y`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelMapping {
+    pub origin: Complex32,
+    pub step_x: Complex32,
+    pub step_y: Complex32,
+}
+
+impl PixelMapping {
+    #[inline]
+    pub fn map(&self, x: f32, y: f32) -> Complex32 {
+        Complex32::new(
+            self.origin.re + x * self.step_x.re + y * self.step_y.re,
+            self.origin.im + x * self.step_x.im + y * self.step_y.im,
+        )
+    }
+
+    /// Pixel coordinates of `c`, the inverse of `map`.
+    pub fn inverse(&self, c: Complex32) -> (f32, f32) {
+        let (re, im) = (c.re - self.origin.re, c.im - self.origin.im);
+        let determinant = self.step_x.re * self.step_y.im - self.step_y.re * self.step_x.im;
+        (
+            (re * self.step_y.im - im * self.step_y.re) / determinant,
+            (im * self.step_x.re - re * self.step_x.im) / determinant,
+        )
+    }
+}
@@ -0,0 +1,80 @@
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use rsfractal_mandelbrot::tiles::TileAddress;
+
+/// Encoded tiles of one style, identified by a hash of its parameters.
+type Key = (u64, TileAddress);
+
+/// Two level tile cache: recently served tiles in memory and, when a
+/// directory is configured, every rendered tile on disk.
+pub(crate) struct TileCache {
+    memory: Mutex<LruCache<Key, Arc<Vec<u8>>>>,
+    directory: Option<PathBuf>,
+}
+
+impl TileCache {
+    pub(crate) fn new(capacity: usize, directory: Option<PathBuf>) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            memory: Mutex::new(LruCache::new(capacity)),
+            directory,
+        }
+    }
+
+    /// Returns the cached tile or stores the result of `render`.
+    pub(crate) fn get_or_render(
+        &self,
+        style: u64,
+        tile: TileAddress,
+        render: impl FnOnce() -> anyhow::Result<Vec<u8>>,
+    ) -> anyhow::Result<Arc<Vec<u8>>> {
+        let key = (style, tile);
+        if let Some(bytes) = self.memory.lock().unwrap().get(&key) {
+            return Ok(bytes.clone());
+        }
+        let path = self
+            .directory
+            .as_deref()
+            .map(|directory| tile_path(directory, style, tile));
+        let bytes = match path.as_deref().and_then(|path| std::fs::read(path).ok()) {
+            Some(bytes) => bytes,
+            None => {
+                let bytes = render()?;
+                if let Some(path) = &path {
+                    // A full disk or a read-only directory only costs the disk cache.
+                    if let Err(error) = write_atomic(path, &bytes) {
+                        eprintln!("failed to cache {}: {error}", path.display());
+                    }
+                }
+                bytes
+            }
+        };
+        let bytes = Arc::new(bytes);
+        self.memory.lock().unwrap().put(key, bytes.clone());
+        Ok(bytes)
+    }
+}
+
+fn tile_path(directory: &Path, style: u64, tile: TileAddress) -> PathBuf {
+    directory
+        .join(format!("{style:016x}"))
+        .join(tile.z.to_string())
+        .join(tile.x.to_string())
+        .join(format!("{}.png", tile.y))
+}
+
+/// Concurrent requests for the same tile must not see a partially written file.
+fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", WRITES.fetch_add(1, Ordering::Relaxed)));
+    std::fs::write(&temporary, bytes)?;
+    std::fs::rename(&temporary, path)
+}
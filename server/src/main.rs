@@ -0,0 +1,221 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, anyhow};
+use clap::Parser;
+use rsfractal_mandelbrot::mandelbrot::{Coloring, Mandelbrot};
+use rsfractal_mandelbrot::tiles::{MAX_ZOOM, TileAddress};
+use strum::IntoEnumIterator;
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::cache::TileCache;
+
+mod cache;
+
+/// Serves the set as slippy-map tiles at /{z}/{x}/{y}.png for Leaflet,
+/// OpenLayers and other map viewers.
+#[derive(Parser)]
+#[command(name = "rsfractal-server", version)]
+struct Args {
+    /// Address to listen on, the default only accepts local connections
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    bind: String,
+    /// JSON, TOML or PNG file with the base parameters, the view is ignored
+    #[arg(short, long)]
+    parameters: Option<PathBuf>,
+    /// Tile width and height in pixels
+    #[arg(long, default_value_t = 256)]
+    tile_size: usize,
+    /// Number of encoded tiles kept in memory
+    #[arg(long, default_value_t = 1024)]
+    cache_size: usize,
+    /// Directory for rendered tiles, reused across restarts
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+    /// Requests handled at the same time, every render also uses all cores
+    #[arg(long, default_value_t = 4)]
+    workers: usize,
+}
+
+struct TileServer {
+    base: Mandelbrot,
+    tile_size: usize,
+    cache: TileCache,
+}
+
+/// Error answered with an HTTP status instead of a tile.
+struct HttpError(u16, String);
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let base = match &args.parameters {
+        Some(path) => Mandelbrot::load(path).with_context(|| format!("failed to load {}", path.display()))?,
+        None => Mandelbrot::default(),
+    };
+    if args.tile_size == 0 {
+        return Err(anyhow!("tile size must not be zero"));
+    }
+    let server = Server::http(&args.bind).map_err(|error| anyhow!("failed to listen on {}: {error}", args.bind))?;
+    let tiles = TileServer {
+        base,
+        tile_size: args.tile_size,
+        cache: TileCache::new(args.cache_size, args.cache_dir),
+    };
+    eprintln!("serving tiles at http://{}/{{z}}/{{x}}/{{y}}.png", args.bind);
+
+    std::thread::scope(|scope| {
+        for _ in 0..args.workers.max(1) {
+            scope.spawn(|| {
+                for request in server.incoming_requests() {
+                    tiles.handle(request);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+impl TileServer {
+    fn handle(&self, request: Request) {
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let response = match path {
+            "/" => Ok(self.index()),
+            path => self.tile(path, query),
+        };
+        let response = response.unwrap_or_else(|HttpError(status, message)| {
+            Response::from_string(message)
+                .with_status_code(status)
+                .with_header(header("Content-Type", "text/plain; charset=utf-8"))
+        });
+        // The client may have gone away, there is nobody left to tell.
+        let _ = request.respond(response.with_header(header("Access-Control-Allow-Origin", "*")));
+    }
+
+    fn tile(&self, path: &str, query: &str) -> Result<Response<std::io::Cursor<Vec<u8>>>, HttpError> {
+        if !path.ends_with(".png") {
+            return Err(HttpError(404, format!("no such resource {path}")));
+        }
+        let tile: TileAddress = path.parse().map_err(|error| HttpError(404, error))?;
+        let style = self.style(query).map_err(|error| HttpError(400, error))?;
+        let key = style_key(&style, self.tile_size);
+        let bytes = self
+            .cache
+            .get_or_render(key, tile, || {
+                let view = tile.view(&style, self.tile_size);
+                let mut pixels = vec![0u8; view.width * view.height * 4];
+                view.render(&mut pixels);
+                Ok(view.encode_png(&pixels)?)
+            })
+            .map_err(|error| HttpError(500, format!("failed to render tile {tile}: {error}")))?;
+        Ok(Response::from_data(bytes.as_ref().clone())
+            .with_header(header("Content-Type", "image/png"))
+            .with_header(header("Cache-Control", "public, max-age=86400")))
+    }
+
+    /// Base parameters with the overrides of the query string applied, in
+    /// the same order as the command-line flags.
+    fn style(&self, query: &str) -> Result<Mandelbrot, String> {
+        let mut style = self.base.clone();
+        let (mut palette, mut coloring) = (None, None);
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value);
+            match name {
+                "palette" => {
+                    let index = style
+                        .palettes()
+                        .iter()
+                        .position(|palette| palette.name.eq_ignore_ascii_case(&value));
+                    palette = Some(index.ok_or_else(|| format!("unknown palette '{value}'"))?);
+                }
+                "coloring" => {
+                    let parsed = value.parse::<Coloring>();
+                    coloring = Some(parsed.map_err(|_| format!("unknown coloring '{value}'"))?);
+                }
+                "iterations" => {
+                    style.max_iterations = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|iterations| *iterations > 0)
+                        .ok_or_else(|| format!("invalid iterations '{value}'"))?;
+                }
+                _ => return Err(format!("unknown query parameter '{name}'")),
+            }
+        }
+        if let Some(palette) = palette {
+            style.selected_palette = palette;
+            if !style.coloring.uses_palette() {
+                style.coloring = Coloring::Palette;
+            }
+        }
+        if let Some(coloring) = coloring {
+            style.coloring = coloring;
+        }
+        Ok(style)
+    }
+
+    fn index(&self) -> Response<std::io::Cursor<Vec<u8>>> {
+        let palettes: Vec<&str> = self
+            .base
+            .palettes()
+            .iter()
+            .map(|palette| palette.name.as_str())
+            .collect();
+        let colorings: Vec<String> = Coloring::iter().map(|coloring| coloring.to_string()).collect();
+        let body = format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>rsfractal tiles</title></head><body>\n\
+             <h1>rsfractal tiles</h1>\n\
+             <p>Tile URL template: <code>/{{z}}/{{x}}/{{y}}.png</code>, {size}&times;{size} pixels, zoom levels 0 to {MAX_ZOOM}.</p>\n\
+             <p>Query parameters: <code>palette</code> ({palettes}), <code>coloring</code> ({colorings}) and <code>iterations</code>.</p>\n\
+             <p><a href=\"/0/0/0.png\">Level 0 tile</a></p>\n\
+             </body></html>\n",
+            size = self.tile_size,
+            palettes = palettes.join(", "),
+            colorings = colorings.join(", "),
+        );
+        Response::from_string(body).with_header(header("Content-Type", "text/html; charset=utf-8"))
+    }
+}
+
+/// Tiles of styles with the same parameters share cache entries. The key
+/// names files of the disk cache, so it is a 64 bit FNV-1a hash, which unlike
+/// the standard library hasher stays the same across Rust releases.
+fn style_key(style: &Mandelbrot, tile_size: usize) -> u64 {
+    let json = style.to_json().unwrap_or_default();
+    json.bytes()
+        .chain((tile_size as u64).to_le_bytes())
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("static header is valid")
+}
+
+/// Decodes `%XX` escapes and `+` as used in query strings.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[index], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                index += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
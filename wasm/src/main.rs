@@ -1,21 +1,37 @@
+use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
 
 use futures::channel::oneshot;
-use js_sys::Uint8ClampedArray;
+use js_sys::{Uint8Array, Uint8ClampedArray};
 use leptos::html::Canvas;
+use leptos::logging::error;
 use leptos::prelude::*;
+use leptos::task::spawn_local;
+use rsfractal_mandelbrot::antialiasing::SamplePattern;
+use rsfractal_mandelbrot::hdr::ToneMapping;
+use rsfractal_mandelbrot::history::{Bookmarks, History, ViewState};
+use rsfractal_mandelbrot::lighting::HeightField;
 use rsfractal_mandelbrot::mandelbrot::*;
+use rsfractal_mandelbrot::orbit_trap::{TrapColoring, TrapShape};
+use rsfractal_mandelbrot::palette::{Blend, Interpolation, PaletteFormat};
+use rsfractal_mandelbrot::parameters::ParameterFormat;
 use serde::Serialize;
 use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
 use web_sys::CanvasRenderingContext2d;
+use web_sys::DragEvent;
 use web_sys::Element;
+use web_sys::HtmlInputElement;
 
 use strum::IntoEnumIterator;
 pub use wasm_bindgen_rayon::init_thread_pool;
 use web_sys::ImageData;
 use web_sys::MouseEvent;
 
+/// Local storage key of the bookmarks.
+const BOOKMARKS_KEY: &str = "rsfractal-bookmarks";
+
 #[derive(Serialize)]
 struct ContextAttributes {
     alpha: bool,
@@ -47,6 +63,45 @@ fn App() -> impl IntoView {
         action.dispatch(mandelbrot.get());
     };
 
+    let (history, set_history) = signal(History::default());
+    let (bookmarks, set_bookmarks) = signal(load_bookmarks());
+    let (bookmark_name, set_bookmark_name) = signal(String::new());
+
+    // Makes the current view the target of the next undo.
+    let record_view = move || {
+        let view = ViewState::of(&mandelbrot.read_untracked());
+        set_history.update(|history| history.record(view));
+    };
+    let show_view = move |view: ViewState| {
+        set_mandelbrot.update(|mandelbrot| view.apply(mandelbrot));
+        render();
+    };
+    let undo = move || {
+        let current = ViewState::of(&mandelbrot.read_untracked());
+        if let Some(view) = set_history.try_update(|history| history.undo(current)).flatten() {
+            show_view(view);
+        }
+    };
+    let redo = move || {
+        let current = ViewState::of(&mandelbrot.read_untracked());
+        if let Some(view) = set_history.try_update(|history| history.redo(current)).flatten() {
+            show_view(view);
+        }
+    };
+
+    let keys = window_event_listener(leptos::ev::keydown, move |event| {
+        if !(event.ctrl_key() || event.meta_key()) || action.pending().get_untracked() {
+            return;
+        }
+        match event.key().as_str() {
+            "z" => undo(),
+            "y" | "Z" => redo(),
+            _ => return,
+        }
+        event.prevent_default();
+    });
+    on_cleanup(move || keys.remove());
+
     Effect::new(move || {
         if let Some(canvas) = canvas_ref.get()
             && let Some(pixels) = action.value().get()
@@ -82,6 +137,7 @@ fn App() -> impl IntoView {
             let scale_y = canvas.height() as f32 / rect.height() as f32;
             let x = (event.client_x() as f32 - rect.left() as f32) * scale_x;
             let y = (event.client_y() as f32 - rect.top() as f32) * scale_y;
+            record_view();
             set_mandelbrot.update(|mandelbrot| {
                 let zoom_factor = if event.shift_key() { 1.0 / 0.25 } else { 0.25 };
                 mandelbrot.zoom(x, y, zoom_factor);
@@ -90,6 +146,38 @@ fn App() -> impl IntoView {
         }
     };
 
+    let on_drop = move |event: DragEvent| {
+        event.prevent_default();
+        let Some(file) = event
+            .data_transfer()
+            .and_then(|data| data.files())
+            .and_then(|files| files.get(0))
+        else {
+            return;
+        };
+        spawn_local(async move {
+            let name = file.name();
+            let Some(format) = ParameterFormat::from_path(Path::new(&name)) else {
+                error!("{name}: unknown parameter format");
+                return;
+            };
+            let Ok(buffer) = JsFuture::from(file.array_buffer()).await else {
+                error!("{name}: failed to read file");
+                return;
+            };
+            match Mandelbrot::decode(format, &Uint8Array::new(&buffer).to_vec()) {
+                Ok(mut loaded) => {
+                    // The trap image is not part of the parameters.
+                    loaded.trap.image = mandelbrot.read_untracked().trap.image.clone();
+                    record_view();
+                    set_mandelbrot.set(loaded);
+                    render();
+                }
+                Err(err) => error!("{name}: {err}"),
+            }
+        });
+    };
+
     view! {
         <main class="size-full">
             <canvas
@@ -98,17 +186,21 @@ fn App() -> impl IntoView {
                 height=move || mandelbrot.read().height
                 node_ref=canvas_ref
                 on:click=on_click
+                on:dragover=|event: DragEvent| event.prevent_default()
+                on:drop=on_drop
             />
             <header class="absolute left-0 top-0 m-4 p-4 rounded-lg bg-slate-700 opacity-50 z-1">
                 <h1 class="text-2xl">"rsfractal"</h1>
                 <hr class="my-2" />
                 <h2 class="text-base">"click to zoom in, shift-click to zoom out"</h2>
+                <h2 class="text-base">"drop a PNG, JSON or TOML file to restore a view"</h2>
                 <hr class="my-2" />
                 <Button on:click=move |_| render() prop:disabled=move || action.pending().get()>
                     {move || if action.pending().get() { "Rendering..." } else { "Render" }}
                 </Button>
                 <Button
                     on:click=move |_| {
+                        record_view();
                         *set_mandelbrot.write() = Mandelbrot::default();
                         render();
                     }
@@ -116,6 +208,95 @@ fn App() -> impl IntoView {
                 >
                     "Reset"
                 </Button>
+                <br />
+                <Button
+                    on:click=move |_| undo()
+                    prop:disabled=move || action.pending().get() || !history.read().can_undo()
+                >
+                    "Back"
+                </Button>
+                <Button
+                    on:click=move |_| redo()
+                    prop:disabled=move || action.pending().get() || !history.read().can_redo()
+                >
+                    "Forward"
+                </Button>
+                <hr class="my-2" />
+                <label class="text-base" for="bookmark_name">
+                    "Bookmark:"
+                </label>
+                <Input
+                    attr:id="bookmark_name"
+                    attr:placeholder="name"
+                    on:input=move |ev| set_bookmark_name.set(event_target_value(&ev))
+                    prop:value=move || bookmark_name.get()
+                />
+                <br />
+                <Button
+                    on:click=move |_| {
+                        let name = bookmark_name.get_untracked();
+                        let name = name.trim();
+                        if !name.is_empty() {
+                            let view = ViewState::of(&mandelbrot.read_untracked());
+                            set_bookmarks
+                                .update(|bookmarks| {
+                                    bookmarks.insert(name, view);
+                                    save_bookmarks(bookmarks);
+                                });
+                        }
+                    }
+                    prop:disabled=move || bookmark_name.read().trim().is_empty()
+                >
+                    "Save"
+                </Button>
+                <Button
+                    on:click=move |_| {
+                        let name = bookmark_name.get_untracked();
+                        set_bookmarks
+                            .update(|bookmarks| {
+                                if bookmarks.remove(name.trim()).is_some() {
+                                    save_bookmarks(bookmarks);
+                                }
+                            });
+                    }
+                    prop:disabled=move || bookmarks.read().get(bookmark_name.read().trim()).is_none()
+                >
+                    "Delete"
+                </Button>
+                <br />
+                <label class="text-base" for="bookmarks">
+                    "Go to:"
+                </label>
+                <Select
+                    attr:id="bookmarks"
+                    on:change=move |ev| {
+                        let name = event_target_value(&ev);
+                        let view = bookmarks.read_untracked().get(&name).map(|bookmark| bookmark.view.clone());
+                        if let Some(view) = view {
+                            set_bookmark_name.set(name);
+                            record_view();
+                            show_view(view);
+                        }
+                    }
+                    prop:disabled=move || action.pending().get()
+                    prop:value=""
+                >
+                    <option value="" selected>
+                        "-"
+                    </option>
+                    {move || {
+                        bookmarks
+                            .read()
+                            .bookmarks
+                            .iter()
+                            .map(|bookmark| {
+                                let value = bookmark.name.clone();
+                                let label = value.clone();
+                                view! { <option value=value>{label}</option> }
+                            })
+                            .collect_view()
+                    }}
+                </Select>
                 <hr class="my-2" />
                 <label class="text-base" for="resolution">
                     "Resolution:"
@@ -269,7 +450,90 @@ fn App() -> impl IntoView {
                         })
                         .collect_view()}
                 </Select>
-                <Show when=move || mandelbrot.read().coloring == Coloring::Palette>
+                <Show when=move || mandelbrot.read().coloring == Coloring::OrbitTrap>
+                    <br />
+                    <label class="text-base" for="trap_shape">
+                        "Trap:"
+                    </label>
+                    <Select
+                        attr:id="trap_shape"
+                        on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            set_mandelbrot
+                                .update(|mandelbrot| {
+                                    mandelbrot.trap.shape = TrapShape::from_str(&value).unwrap();
+                                });
+                            render()
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:value=move || mandelbrot.read().trap.shape.to_string()
+                    >
+                        {TrapShape::iter()
+                            .filter(|shape| *shape != TrapShape::Image)
+                            .map(|shape| {
+                                view! {
+                                    <option
+                                        value=shape.to_string()
+                                        selected=move || mandelbrot.read().trap.shape == shape
+                                    >
+                                        {shape.to_string()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </Select>
+                    <Select
+                        attr:id="trap_coloring"
+                        on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            set_mandelbrot
+                                .update(|mandelbrot| {
+                                    mandelbrot.trap.coloring = TrapColoring::from_str(&value).unwrap();
+                                });
+                            render()
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:value=move || mandelbrot.read().trap.coloring.to_string()
+                    >
+                        {TrapColoring::iter()
+                            .map(|coloring| {
+                                view! {
+                                    <option
+                                        value=coloring.to_string()
+                                        selected=move || mandelbrot.read().trap.coloring == coloring
+                                    >
+                                        {coloring.to_string()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </Select>
+                    <br />
+                </Show>
+                <Show when=move || mandelbrot.read().coloring == Coloring::Stripe>
+                    <br />
+                    <label class="text-base" for="stripe_density">
+                        "Stripe Density:"
+                    </label>
+                    <Input
+                        attr:id="stripe_density"
+                        attr:r#type="number"
+                        attr:min=1
+                        on:change=move |ev| {
+                            if let Ok(value) = event_target_value(&ev).parse() {
+                                set_mandelbrot
+                                    .update(|mandelbrot| {
+                                        mandelbrot.stripe_density = value;
+                                    });
+                                render()
+                            }
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:value=move || mandelbrot.read().stripe_density
+                    />
+                    <br />
+                </Show>
+                <Show when=move || mandelbrot.read().coloring.uses_palette()>
                     <Select
                         attr:id="palette"
                         on:change=move |ev| {
@@ -280,23 +544,220 @@ fn App() -> impl IntoView {
                         prop:disabled=move || action.pending().get()
                         prop:value=move || { mandelbrot.read().selected_palette.to_string() }
                     >
-                        {mandelbrot
-                            .read_untracked()
-                            .palettes()
-                            .iter()
-                            .enumerate()
-                            .map(|(index, (name, _))| {
+                        {move || {
+                            mandelbrot
+                                .read()
+                                .palettes()
+                                .iter()
+                                .enumerate()
+                                .map(|(index, palette)| {
+                                    view! {
+                                        <option
+                                            value=index.to_string()
+                                            selected=move || mandelbrot.read().selected_palette == index
+                                        >
+                                            {palette.name.clone()}
+                                        </option>
+                                    }
+                                })
+                                .collect_view()
+                        }}
+                    </Select>
+                    <Button
+                        on:click=move |_| {
+                            set_mandelbrot
+                                .update(|mandelbrot| {
+                                    mandelbrot.remove_palette(mandelbrot.selected_palette);
+                                });
+                            render();
+                        }
+                        prop:disabled=move || {
+                            action.pending().get() || mandelbrot.read().palettes().len() <= 1
+                        }
+                    >
+                        "Delete"
+                    </Button>
+                    <br />
+                    <label class="text-base" for="palette_interpolation">
+                        "Interpolation:"
+                    </label>
+                    <Select
+                        attr:id="palette_interpolation"
+                        on:change=move |ev| {
+                            let value = Interpolation::from_str(&event_target_value(&ev)).unwrap();
+                            set_mandelbrot
+                                .update(|mandelbrot| {
+                                    let selected = mandelbrot.selected_palette;
+                                    if let Some(palette) = mandelbrot.palette_mut(selected)
+                                        && let Err(err) = palette.set_interpolation(value)
+                                    {
+                                        error!("{err}");
+                                    }
+                                });
+                            render();
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:value=move || {
+                            let mandelbrot = mandelbrot.read();
+                            mandelbrot.palettes()[mandelbrot.selected_palette].interpolation().to_string()
+                        }
+                    >
+                        {Interpolation::iter()
+                            .map(|interpolation| {
                                 view! {
                                     <option
-                                        value=index.to_string()
-                                        selected=move || mandelbrot.read().selected_palette == index
+                                        value=interpolation.to_string()
+                                        selected=move || {
+                                            let mandelbrot = mandelbrot.read();
+                                            mandelbrot.palettes()[mandelbrot.selected_palette].interpolation()
+                                                == interpolation
+                                        }
                                     >
-                                        {name.clone()}
+                                        {interpolation.to_string()}
                                     </option>
                                 }
                             })
                             .collect_view()}
                     </Select>
+                    <br />
+                    <label class="text-base" for="palette_blend">
+                        "Blend:"
+                    </label>
+                    <Select
+                        attr:id="palette_blend"
+                        on:change=move |ev| {
+                            let value = Blend::from_str(&event_target_value(&ev)).unwrap();
+                            set_mandelbrot
+                                .update(|mandelbrot| {
+                                    let selected = mandelbrot.selected_palette;
+                                    if let Some(palette) = mandelbrot.palette_mut(selected)
+                                        && let Err(err) = palette.set_blend(value)
+                                    {
+                                        error!("{err}");
+                                    }
+                                });
+                            render();
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:value=move || {
+                            let mandelbrot = mandelbrot.read();
+                            mandelbrot.palettes()[mandelbrot.selected_palette].blend().to_string()
+                        }
+                    >
+                        {Blend::iter()
+                            .map(|blend| {
+                                view! {
+                                    <option
+                                        value=blend.to_string()
+                                        selected=move || {
+                                            let mandelbrot = mandelbrot.read();
+                                            mandelbrot.palettes()[mandelbrot.selected_palette].blend() == blend
+                                        }
+                                    >
+                                        {blend.to_string()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </Select>
+                    <br />
+                    <label class="text-base" for="palette_offset">
+                        "Offset:"
+                    </label>
+                    <Input
+                        attr:id="palette_offset"
+                        attr:r#type="number"
+                        attr:step="0.05"
+                        on:change=move |ev| {
+                            if let Ok(value) = event_target_value(&ev).parse() {
+                                set_mandelbrot
+                                    .update(|mandelbrot| {
+                                        mandelbrot.palette_offset = value;
+                                    });
+                                render();
+                            }
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:value=move || mandelbrot.read().palette_offset
+                    />
+                    <br />
+                    <label class="text-base" for="palette_repeat">
+                        "Repeat:"
+                    </label>
+                    <Input
+                        attr:id="palette_repeat"
+                        attr:r#type="number"
+                        attr:min=1
+                        on:change=move |ev| {
+                            if let Ok(value) = event_target_value(&ev).parse() {
+                                set_mandelbrot
+                                    .update(|mandelbrot| {
+                                        mandelbrot.palette_repeat = value;
+                                    });
+                                render();
+                            }
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:value=move || mandelbrot.read().palette_repeat
+                    />
+                    <br />
+                    <label class="text-base" for="palette_reverse">
+                        "Reverse:"
+                    </label>
+                    <input
+                        id="palette_reverse"
+                        type="checkbox"
+                        class="my-1 ml-2"
+                        on:change=move |ev| {
+                            let value = event_target_checked(&ev);
+                            set_mandelbrot.update(|mandelbrot| mandelbrot.palette_reverse = value);
+                            render();
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:checked=move || mandelbrot.read().palette_reverse
+                    />
+                    <br />
+                    <label class="text-base" for="palette_file">
+                        "Import Palette:"
+                    </label>
+                    <input
+                        id="palette_file"
+                        type="file"
+                        accept=".ggr,.map,.cpt,.svg"
+                        class="my-1 ml-2 w-48"
+                        on:change=move |ev| {
+                            let input: HtmlInputElement = event_target(&ev);
+                            if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                                spawn_local(async move {
+                                    let name = file.name();
+                                    let path = Path::new(&name);
+                                    let Some(format) = PaletteFormat::from_path(path) else {
+                                        error!("{name}: unknown palette format");
+                                        return;
+                                    };
+                                    let stem = path
+                                        .file_stem()
+                                        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+                                    let Some(source) = JsFuture::from(file.text())
+                                        .await
+                                        .ok()
+                                        .and_then(|text| text.as_string()) else {
+                                        error!("{name}: failed to read file");
+                                        return;
+                                    };
+                                    set_mandelbrot
+                                        .update(|mandelbrot| {
+                                            match mandelbrot.import_palette(format, &stem, &source) {
+                                                Ok(index) => mandelbrot.selected_palette = index,
+                                                Err(err) => error!("{name}: {err}"),
+                                            }
+                                        });
+                                    render();
+                                });
+                            }
+                        }
+                        prop:disabled=move || action.pending().get()
+                    />
                 </Show>
                 <br />
                 <label class="text-base" for="exponent">
@@ -318,11 +779,296 @@ fn App() -> impl IntoView {
                     prop:disabled=move || action.pending().get()
                     prop:value=move || mandelbrot.read().exponent
                 />
+                <hr class="my-2" />
+                <label class="text-base" for="lighting">
+                    "Lighting:"
+                </label>
+                <input
+                    id="lighting"
+                    type="checkbox"
+                    class="my-1 ml-2"
+                    on:change=move |ev| {
+                        let value = event_target_checked(&ev);
+                        set_mandelbrot.update(|mandelbrot| mandelbrot.lighting.enabled = value);
+                        render();
+                    }
+                    prop:disabled=move || action.pending().get()
+                    prop:checked=move || mandelbrot.read().lighting.enabled
+                />
+                <Show when=move || mandelbrot.read().lighting.enabled>
+                    <Select
+                        attr:id="height_field"
+                        on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            set_mandelbrot
+                                .update(|mandelbrot| {
+                                    mandelbrot.lighting.height_field = HeightField::from_str(&value)
+                                        .unwrap();
+                                });
+                            render()
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:value=move || mandelbrot.read().lighting.height_field.to_string()
+                    >
+                        {HeightField::iter()
+                            .map(|height_field| {
+                                view! {
+                                    <option
+                                        value=height_field.to_string()
+                                        selected=move || {
+                                            mandelbrot.read().lighting.height_field == height_field
+                                        }
+                                    >
+                                        {height_field.to_string()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </Select>
+                    <br />
+                    <label class="text-base" for="light_angle">
+                        "Light Angle:"
+                    </label>
+                    <Input
+                        attr:id="light_angle"
+                        attr:r#type="number"
+                        on:change=move |ev| {
+                            if let Ok(value) = event_target_value(&ev).parse() {
+                                set_mandelbrot
+                                    .update(|mandelbrot| {
+                                        mandelbrot.lighting.angle = value;
+                                    });
+                                render();
+                            }
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:value=move || mandelbrot.read().lighting.angle
+                    />
+                    <br />
+                    <label class="text-base" for="light_elevation">
+                        "Light Elevation:"
+                    </label>
+                    <Input
+                        attr:id="light_elevation"
+                        attr:r#type="number"
+                        attr:min=0
+                        attr:max=90
+                        on:change=move |ev| {
+                            if let Ok(value) = event_target_value(&ev).parse() {
+                                set_mandelbrot
+                                    .update(|mandelbrot| {
+                                        mandelbrot.lighting.elevation = value;
+                                    });
+                                render();
+                            }
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:value=move || mandelbrot.read().lighting.elevation
+                    />
+                    <br />
+                    <label class="text-base" for="light_ambient">
+                        "Ambient:"
+                    </label>
+                    <Input
+                        attr:id="light_ambient"
+                        attr:r#type="number"
+                        attr:step="0.05"
+                        attr:min=0
+                        on:change=move |ev| {
+                            if let Ok(value) = event_target_value(&ev).parse() {
+                                set_mandelbrot
+                                    .update(|mandelbrot| {
+                                        mandelbrot.lighting.ambient = value;
+                                    });
+                                render();
+                            }
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:value=move || mandelbrot.read().lighting.ambient
+                    />
+                </Show>
+                <br />
+                <label class="text-base" for="antialiasing">
+                    "Antialiasing:"
+                </label>
+                <Select
+                    attr:id="antialiasing"
+                    on:change=move |ev| {
+                        if let Ok(value) = event_target_value(&ev).parse() {
+                            set_mandelbrot.update(|mandelbrot| mandelbrot.antialiasing.samples = value);
+                            render();
+                        }
+                    }
+                    prop:value=move || mandelbrot.read().antialiasing.samples.to_string()
+                    prop:disabled=move || action.pending().get()
+                >
+                    {(1..=4)
+                        .map(|samples| {
+                            view! {
+                                <option
+                                    value=samples.to_string()
+                                    selected=move || mandelbrot.read().antialiasing.samples == samples
+                                >
+                                    {if samples == 1 {
+                                        "Off".to_string()
+                                    } else {
+                                        format!("{samples}x{samples}")
+                                    }}
+                                </option>
+                            }
+                        })
+                        .collect_view()}
+                </Select>
+                <Show when=move || mandelbrot.read().antialiasing.enabled()>
+                    <Select
+                        attr:id="sample_pattern"
+                        on:change=move |ev| {
+                            let value = event_target_value(&ev);
+                            set_mandelbrot
+                                .update(|mandelbrot| {
+                                    mandelbrot.antialiasing.pattern = SamplePattern::from_str(&value)
+                                        .unwrap();
+                                });
+                            render();
+                        }
+                        prop:value=move || mandelbrot.read().antialiasing.pattern.to_string()
+                        prop:disabled=move || action.pending().get()
+                    >
+                        {SamplePattern::iter()
+                            .map(|pattern| {
+                                view! {
+                                    <option
+                                        value=pattern.to_string()
+                                        selected=move || {
+                                            mandelbrot.read().antialiasing.pattern == pattern
+                                        }
+                                    >
+                                        {pattern.to_string()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </Select>
+                    <br />
+                    <label class="text-base" for="adaptive">
+                        "Adaptive:"
+                    </label>
+                    <input
+                        id="adaptive"
+                        type="checkbox"
+                        class="my-1 ml-2"
+                        on:change=move |ev| {
+                            let value = event_target_checked(&ev);
+                            set_mandelbrot.update(|mandelbrot| mandelbrot.antialiasing.adaptive = value);
+                            render();
+                        }
+                        prop:disabled=move || action.pending().get()
+                        prop:checked=move || mandelbrot.read().antialiasing.adaptive
+                    />
+                </Show>
+                <br />
+                <label class="text-base" for="tone_mapping">
+                    "Tone Mapping:"
+                </label>
+                <Select
+                    attr:id="tone_mapping"
+                    on:change=move |ev| {
+                        let value = event_target_value(&ev);
+                        set_mandelbrot
+                            .update(|mandelbrot| {
+                                mandelbrot.tone_mapping.operator = ToneMapping::from_str(&value)
+                                    .unwrap();
+                            });
+                        render();
+                    }
+                    prop:value=move || mandelbrot.read().tone_mapping.operator.to_string()
+                    prop:disabled=move || action.pending().get()
+                >
+                    {ToneMapping::iter()
+                        .map(|operator| {
+                            view! {
+                                <option
+                                    value=operator.to_string()
+                                    selected=move || {
+                                        mandelbrot.read().tone_mapping.operator == operator
+                                    }
+                                >
+                                    {operator.to_string()}
+                                </option>
+                            }
+                        })
+                        .collect_view()}
+                </Select>
+                <br />
+                <label class="text-base" for="exposure">
+                    "Exposure:"
+                </label>
+                <Input
+                    attr:id="exposure"
+                    attr:r#type="number"
+                    attr:step="0.5"
+                    on:change=move |ev| {
+                        if let Ok(value) = event_target_value(&ev).parse() {
+                            set_mandelbrot
+                                .update(|mandelbrot| {
+                                    mandelbrot.tone_mapping.exposure = value;
+                                });
+                            render();
+                        }
+                    }
+                    prop:disabled=move || action.pending().get()
+                    prop:value=move || mandelbrot.read().tone_mapping.exposure
+                />
+                <br />
+                <label class="text-base" for="dither">
+                    "Dither:"
+                </label>
+                <input
+                    id="dither"
+                    type="checkbox"
+                    class="my-1 ml-2"
+                    on:change=move |ev| {
+                        let value = event_target_checked(&ev);
+                        set_mandelbrot.update(|mandelbrot| mandelbrot.tone_mapping.dither = value);
+                        render();
+                    }
+                    prop:disabled=move || action.pending().get()
+                    prop:checked=move || mandelbrot.read().tone_mapping.dither
+                />
             </header>
         </main>
     }
 }
 
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+fn load_bookmarks() -> Bookmarks {
+    let json = local_storage().and_then(|storage| storage.get_item(BOOKMARKS_KEY).ok().flatten());
+    match json.map(|json| Bookmarks::from_json(&json)) {
+        Some(Ok(bookmarks)) => bookmarks,
+        Some(Err(err)) => {
+            error!("{BOOKMARKS_KEY}: {err}");
+            Bookmarks::default()
+        }
+        None => Bookmarks::default(),
+    }
+}
+
+fn save_bookmarks(bookmarks: &Bookmarks) {
+    let json = match bookmarks.to_json() {
+        Ok(json) => json,
+        Err(err) => return error!("{BOOKMARKS_KEY}: {err}"),
+    };
+    if local_storage()
+        .and_then(|storage| storage.set_item(BOOKMARKS_KEY, &json).ok())
+        .is_none()
+    {
+        error!("{BOOKMARKS_KEY}: local storage is not available");
+    }
+}
+
 #[component]
 fn Button(children: Children) -> impl IntoView {
     view! {